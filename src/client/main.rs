@@ -6,9 +6,13 @@ use clap::{Parser, Subcommand};
 use models::ClientRoomType;
 use shared::api::APIClient;
 use shared::connections::SteckerWebRTCConnection;
-use shared::models::{DataRoomInternalType, SteckerData};
+use shared::models::{unix_timestamp_millis, DataRoomInternalType, MetaMessage, SteckerData};
 
 const LOCAL_HOST: &str = "http://127.0.0.1:8000";
+/// time between the values sent into a created room
+const VALUE_INTERVAL: Duration = Duration::from_secs(5);
+/// time between pings to measure the round trip time to the server
+const PING_INTERVAL: Duration = Duration::from_secs(30);
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -117,6 +121,7 @@ async fn create_room(
         .create_data_channel(&DataRoomInternalType::Meta)
         .await?;
     let mut meta_msg_receiver = meta_data_channel.inbound.subscribe();
+    let meta_outbound = meta_data_channel.outbound.clone();
 
     let data_channel = connection.create_data_channel(&room_type).await?;
     let data_outbound = data_channel.outbound.clone();
@@ -137,20 +142,29 @@ async fn create_room(
 
             println!("Press ctrl-c to stop");
 
-            loop {
-                let timeout = tokio::time::sleep(Duration::from_secs(5));
-                tokio::pin!(timeout);
+            let mut ping_interval = tokio::time::interval(PING_INTERVAL);
+            let mut value_interval = tokio::time::interval_at(
+                tokio::time::Instant::now() + VALUE_INTERVAL,
+                VALUE_INTERVAL,
+            );
 
+            loop {
                 tokio::select! {
-                    _ = timeout.as_mut() =>{
+                    _ = value_interval.tick() =>{
                         println!("Send value: {value}");
                         let _ = data_outbound.send(value.clone());
                     },
+                    _ = ping_interval.tick() => {
+                        let _ = meta_outbound.send(MetaMessage::Ping { timestamp: unix_timestamp_millis() }.encode());
+                    },
                     raw_meta_msg = meta_msg_receiver.recv() => {
-                        if let Ok(SteckerData::String(msg)) = raw_meta_msg {
-                            println!("META: {msg}");
-                        } else {
-                            println!("Error while receiving meta message");
+                        match raw_meta_msg.map(|msg| MetaMessage::decode(&msg)) {
+                            Ok(Ok(MetaMessage::Pong { ping_timestamp, .. })) => {
+                                println!("Round trip time: {}ms", unix_timestamp_millis() - ping_timestamp);
+                            },
+                            Ok(Ok(msg)) => println!("META: {msg}"),
+                            Ok(Err(err)) => println!("Invalid meta message: {err}"),
+                            Err(_) => println!("Error while receiving meta message"),
                         }
                     }
                     _ = tokio::signal::ctrl_c() => {
@@ -215,10 +229,10 @@ async fn join_room(
                         }
                     }
                     raw_meta_msg = meta_receiver.recv() => {
-                        if let Ok(SteckerData::String(msg)) = raw_meta_msg {
-                            println!("META: {msg}");
-                        } else {
-                            println!("Error while receiving meta message");
+                        match raw_meta_msg.map(|msg| MetaMessage::decode(&msg)) {
+                            Ok(Ok(msg)) => println!("META: {msg}"),
+                            Ok(Err(err)) => println!("Invalid meta message: {err}"),
+                            Err(_) => println!("Error while receiving meta message"),
                         }
                     },
                     _ = close_receiver.recv() => {
@@ -26,9 +26,20 @@ SteckerOSC {
 	classvar <>onRoomUpdated;
 	classvar <>onRoomDeleted;
 	classvar <>onRoomMessage;
+	// called with the name of the source room and the return room
+	// which has been reserved for a dispatched listener
+	classvar <>onReturnRoom;
+	// called with the name of the room and the feedback
+	// a listener sent to the sender of the room
+	classvar <>onRoomFeedback;
 
 	classvar <>onDispatcherCreated;
+	classvar <>onDispatcherUpdated;
 	classvar <>onDispatcherDeleted;
+	// called with dispatcherName and number of waiting visitors
+	classvar <>onDispatcherQueue;
+	// called with the seconds until the server closes all connections
+	classvar <>onServerShutdown;
 
 	classvar <lastPing;
 
@@ -59,11 +70,23 @@ SteckerOSC {
 					if(newsCallbacks[msg[1]].notNil, {
 						newsCallbacks[msg[1]].value(*msg[2..]);
 					});
+					if(msg[2] == \returnRoom, {
+						onReturnRoom.value(msg[1], msg[3]);
+					});
+					if(msg[2] == \feedback, {
+						onRoomFeedback.value(msg[1], msg[3]);
+					});
 					onRoomMessage.value(*msg[1..]);
 				}
 
 				{"/createdDispatcher".asSymbol} {onDispatcherCreated.value(*msg[1..])}
+				{"/updatedDispatcher".asSymbol} {onDispatcherUpdated.value(*msg[1..])}
 				{"/deletedDispatcher".asSymbol} {onDispatcherDeleted.value(*msg[1..])}
+				{"/dispatcherQueue".asSymbol} {onDispatcherQueue.value(*msg[1..])}
+				{"/serverShutdown".asSymbol} {
+					"Stecker server shuts down in % seconds".format(msg[1]).warn;
+					onServerShutdown.value(*msg[1..]);
+				}
 			});
 		}
 	}
@@ -101,7 +124,7 @@ SteckerOSC {
 		});
 	}
 
-	*createDispatcher {|name, password, rule, timeout=1000, dispatcherType=nil, returnRoomPrefix=nil|
+	*createDispatcher {|name, password, rule, timeout=1000, dispatcherType=nil, returnRoomPrefix=nil, addRandomPostfix=false, maxListeners=1, stickyTimeout=0|
 		if(SteckerOSC.connected.not, {
 			"SteckerOSC is not connected".warn;
 			^this;
@@ -114,6 +137,9 @@ SteckerOSC {
 			timeout.asInteger,
 			returnRoomPrefix,
 			dispatcherType ? SteckerOSC.dispatcherNextRandom,
+			addRandomPostfix.asInteger,
+			maxListeners.asInteger,
+			stickyTimeout.asInteger,
 		);
 	}
 
@@ -132,6 +158,19 @@ SteckerOSC {
 	*dispatcherNextRandom {
 		^"nextfreerandom";
 	}
+
+	*dispatcherLeastListeners {
+		^"leastlisteners";
+	}
+
+	*dispatcherRoundRobin {
+		^"roundrobin";
+	}
+
+	// use the maxListeners argument of createDispatcher to set the capacity
+	*dispatcherMaxListeners {
+		^"maxlisteners";
+	}
 }
 
 DataSteckerIn : UGen {
@@ -13,7 +13,7 @@ use tokio::sync::broadcast::{self, Receiver, Sender};
 use shared::{
     api::APIClient,
     connections::SteckerWebRTCConnection,
-    models::{DataRoomInternalType, SteckerData},
+    models::{DataRoomInternalType, MetaMessage, SteckerData},
 };
 use tracing::{error, info, info_span, instrument, trace, Level};
 use tracing_subscriber::layer::SubscriberExt;
@@ -22,6 +22,23 @@ use tracing_subscriber::{self, filter, fmt};
 
 use webrtc::media::Sample;
 
+/// posts messages of the meta channel, frequent messages are only traced
+fn log_meta_message(data: &SteckerData) {
+    match MetaMessage::decode(data) {
+        Ok(MetaMessage::Feedback { message }) => {
+            info!(feedback = message, "Received feedback from a listener")
+        }
+        Ok(
+            msg @ (MetaMessage::ListenerCount { .. }
+            | MetaMessage::SenderChanged { .. }
+            | MetaMessage::RoomClosing { .. }
+            | MetaMessage::ServerNotice { .. }),
+        ) => info!("{msg}"),
+        Ok(msg) => trace!(%msg, "Received meta message"),
+        Err(err) => error!(?err, "Received an invalid meta message"),
+    }
+}
+
 fn setup_tracing() {
     let filter = filter::Targets::new()
         .with_default(Level::ERROR)
@@ -105,8 +122,8 @@ impl DataRoom {
                                     }
                                 },
                                 meta_msg = meta_receiver.recv() => {
-                                    if let Ok(SteckerData::String(m)) = meta_msg {
-                                        info!(message=m, "New meta message");
+                                    if let Ok(m) = meta_msg {
+                                        log_meta_message(&m);
                                     } else {
                                         error!("Error on receiving meta message")
                                     }
@@ -150,7 +167,9 @@ impl DataRoom {
 
         let (sender, mut receiver) = broadcast::channel::<f32>(1024);
         let sender2 = sender.clone();
-        let receiver2 = sender2.clone().subscribe();
+
+        // values the server replies with, e.g. the mean of a crowd room
+        let (reply_sender, reply_receiver) = broadcast::channel::<f32>(1024);
 
         let (close_sender, _) = broadcast::channel::<()>(1);
         let mut sc_close_receiver = close_sender.subscribe();
@@ -163,13 +182,41 @@ impl DataRoom {
                 let stecker_data_channel = connection
                     .create_data_channel(&DataRoomInternalType::Float)
                     .await?;
+                let meta_channel = connection
+                    .create_data_channel(&DataRoomInternalType::Meta)
+                    .await?;
                 let offer = connection.create_offer().await?;
 
+                // feedback of the listeners gets posted
+                let span3 = span2.clone();
+                let mut meta_recv = meta_channel.inbound.subscribe();
+                let mut meta_close_receiver = meta_channel.close.subscribe();
+                tokio::spawn(async move {
+                    let _guard = span3.enter();
+                    loop {
+                        tokio::select! {
+                            meta_msg = meta_recv.recv() => {
+                                match meta_msg {
+                                    Ok(m) => log_meta_message(&m),
+                                    Err(_) => break,
+                                }
+                            },
+                            _ = meta_close_receiver.recv() => break,
+                        }
+                    }
+                });
+
                 tokio::spawn(async move {
                     let _guard = span2.enter();
                     let mut webrtc_close_receiver = stecker_data_channel.close.clone().subscribe();
+                    let mut inbound_receiver = stecker_data_channel.inbound.subscribe();
                     loop {
                         tokio::select! {
+                            msg = inbound_receiver.recv() => {
+                                if let Ok(SteckerData::F32(m)) = msg {
+                                    let _ = reply_sender.send(m);
+                                }
+                            },
                             msg_result = receiver.recv() =>{
                                 match msg_result {
                                     Ok(msg) => {
@@ -211,9 +258,9 @@ impl DataRoom {
 
         let room = Self {
             name: name,
-            receiver: receiver2,
+            receiver: reply_receiver,
             sender: sender2,
-            last_value: -1.0,
+            last_value: 0.0,
             close_sender,
         };
 
@@ -240,9 +287,10 @@ impl DataRoom {
         self.last_value
     }
 
+    /// returns the last value the server replied with, e.g. the mean of a crowd room
     pub fn send_message(&mut self, value: f32) -> f32 {
         let _ = self.sender.send(value);
-        1.0
+        self.recv_message()
     }
 }
 
@@ -297,7 +345,7 @@ impl AudioRoomSender {
                         tokio::select! {
                             meta_msg = meta_recv.recv() =>{
                                 if let Ok(msg) = meta_msg {
-                                    info!(message=?msg, "Received meta message");
+                                    log_meta_message(&msg);
                                 }
                             },
                             _ = webrtc_close_receiver.recv() => {
@@ -436,7 +484,7 @@ impl AudioRoomReceiver {
                         tokio::select! {
                             meta_msg = meta_recv.recv() =>{
                                 if let Ok(msg) = meta_msg {
-                                    info!(message=?msg, "Received meta message");
+                                    log_meta_message(&msg);
                                 }
                             },
                             _ = webrtc_close_receiver.recv() => {
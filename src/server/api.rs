@@ -0,0 +1,419 @@
+//! versioned REST API for clients which can not speak GraphQL,
+//! uses the same [`AppState`] operations as the GraphQL schema
+
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use rand::distributions::{Alphanumeric, DistString};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use shared::models::SteckerData;
+use tokio::time::sleep;
+use tracing::{info_span, Instrument};
+
+use crate::{
+    models::{
+        CrowdInput, JamInput, JamParticipantInput, Room, RoomCreationReply, RoomDispatcher,
+        RoomDispatcherInput, RoomDispatcherUpdate, RoomType, ServerInfo,
+    },
+    state::{AppState, StateError},
+};
+
+/// maximum number of values which can be pushed with a single request
+const MAX_PUSHED_VALUES: usize = 1024;
+/// maximum time between the first and the last timestamp of pushed values
+const MAX_PUSHED_SPAN: Duration = Duration::from_secs(60);
+
+/// OpenAPI description of all routes of [`router`]
+const OPENAPI_DOCUMENT: &str = include_str!("openapi.json");
+
+/// routes which get nested under `/api/v1`
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/openapi.json", get(openapi_view))
+        .route("/info", get(server_info))
+        .route("/rooms", get(list_rooms))
+        .route("/rooms/:room_type/:name", get(get_room).post(create_room))
+        .route("/rooms/:room_type/:name/join", post(join_room))
+        .route("/rooms/float/:name/values", post(push_values))
+        .route(
+            "/dispatchers",
+            get(list_dispatchers).post(create_dispatcher),
+        )
+        .route(
+            "/dispatchers/:name",
+            get(get_dispatcher).patch(update_dispatcher),
+        )
+        .route("/dispatchers/:name/access", post(access_dispatcher))
+}
+
+/// errors are returned as `{"error": "..."}`
+pub struct ApiError {
+    status: StatusCode,
+    error: anyhow::Error,
+}
+
+impl ApiError {
+    fn not_found(message: String) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            error: anyhow::anyhow!(message),
+        }
+    }
+
+    fn unauthorized(message: &str) -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            error: anyhow::anyhow!(message.to_string()),
+        }
+    }
+}
+
+impl<E: Into<anyhow::Error>> From<E> for ApiError {
+    fn from(error: E) -> Self {
+        let error = error.into();
+        let status = match error.downcast_ref::<StateError>() {
+            Some(StateError::NotFound(_)) => StatusCode::NOT_FOUND,
+            Some(StateError::NotSupported(_)) => StatusCode::NOT_IMPLEMENTED,
+            None => StatusCode::BAD_REQUEST,
+        };
+        Self { status, error }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(json!({"error": format!("{:#}", self.error)})),
+        )
+            .into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+fn parse_room_type(room_type: String) -> Result<RoomType, ApiError> {
+    RoomType::try_from(room_type.clone())
+        .map_err(|_| ApiError::not_found(format!("Unknown room type {room_type}")))
+}
+
+async fn openapi_view() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "application/json")],
+        OPENAPI_DOCUMENT,
+    )
+}
+
+async fn server_info(State(state): State<Arc<AppState>>) -> Json<ServerInfo> {
+    Json(state.server_info())
+}
+
+#[derive(Deserialize, Debug)]
+struct RoomFilter {
+    room_type: Option<String>,
+}
+
+async fn list_rooms(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<RoomFilter>,
+) -> ApiResult<Vec<Room>> {
+    let room_types = match filter.room_type {
+        Some(room_type) => vec![RoomType::try_from(room_type.clone())
+            .map_err(|_| anyhow::anyhow!("Unknown room type {room_type}"))?],
+        None => vec![RoomType::Float, RoomType::Chat, RoomType::Audio],
+    };
+    let mut rooms = vec![];
+    for room_type in room_types {
+        rooms.extend(state.get_rooms(&room_type).await);
+    }
+    Ok(Json(rooms))
+}
+
+async fn get_room(
+    State(state): State<Arc<AppState>>,
+    Path((room_type, name)): Path<(String, String)>,
+) -> ApiResult<Room> {
+    let room_type = parse_room_type(room_type)?;
+    state
+        .get_rooms(&room_type)
+        .await
+        .into_iter()
+        .find(|room| room.name == name)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("No such room {name}")))
+}
+
+#[derive(Deserialize, Debug)]
+struct CreateRoomRequest {
+    offer: String,
+    password: Option<String>,
+    /// seconds without a signal from the sender after which the room gets deleted
+    inactivity_timeout: Option<i32>,
+    /// unix timestamp (in seconds) at which the room gets deleted
+    expires_at: Option<i64>,
+    /// number of recent messages new listeners of a data room receive on joining
+    replay_length: Option<i32>,
+    /// turns a data room into a jam room, in which listeners may send as well
+    jam: Option<JamInput>,
+    /// turns a float room into a crowd room, in which the values of all listeners get aggregated
+    crowd: Option<CrowdInput>,
+}
+
+async fn create_room(
+    State(state): State<Arc<AppState>>,
+    Path((room_type, name)): Path<(String, String)>,
+    Json(request): Json<CreateRoomRequest>,
+) -> ApiResult<RoomCreationReply> {
+    let room_type = parse_room_type(room_type)?;
+    let reply = state
+        .create_room(
+            name,
+            request.offer,
+            room_type,
+            request.password,
+            request.inactivity_timeout,
+            request.expires_at,
+            request.replay_length,
+            request.jam,
+            request.crowd,
+        )
+        .await?;
+    Ok(Json(reply))
+}
+
+#[derive(Deserialize, Debug)]
+struct JoinRoomRequest {
+    offer: String,
+    /// allows the listener to send into a jam room
+    jam: Option<JamParticipantInput>,
+}
+
+#[derive(Serialize, Debug)]
+struct JoinRoomReply {
+    offer: String,
+}
+
+async fn join_room(
+    State(state): State<Arc<AppState>>,
+    Path((room_type, name)): Path<(String, String)>,
+    Json(request): Json<JoinRoomRequest>,
+) -> ApiResult<JoinRoomReply> {
+    let room_type = parse_room_type(room_type)?;
+    if !state.room_exists(&name, &room_type).await {
+        return Err(ApiError::not_found(format!("No such room {name}")));
+    }
+    let offer = state
+        .join_room(&name, &request.offer, &room_type, request.jam)
+        .await?;
+    Ok(Json(JoinRoomReply { offer }))
+}
+
+#[derive(Deserialize, Debug)]
+struct TimedValue {
+    value: f32,
+    /// unix timestamp in milliseconds
+    timestamp: Option<u64>,
+}
+
+/// a single value, e.g. `0.5`, or a batch of values with timestamps,
+/// e.g. `[{"value": 0.5, "timestamp": 1718000000000}, ...]`
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum PushedValues {
+    Single(f32),
+    Timed(TimedValue),
+    Batch(Vec<TimedValue>),
+}
+
+#[derive(Serialize, Debug)]
+struct PushValuesReply {
+    values: usize,
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+/// Broadcasts values into a float room, the room password is used as bearer token.
+/// A float room owned by the server gets created if the room does not exist.
+/// Mounted at `/rooms/float/:name/values` rather than `/rooms/:name/value`, which
+/// would shadow `/rooms/:room_type/:name` for rooms named `value`.
+/// Batched values are sorted by their timestamps and broadcasted with their
+/// original spacing.
+async fn push_values(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    Json(values): Json<PushedValues>,
+) -> ApiResult<PushValuesReply> {
+    let password = bearer_token(&headers)
+        .ok_or_else(|| ApiError::unauthorized("The room password is required as bearer token"))?;
+    if state.room_exists(&name, &RoomType::Float).await
+        && !state
+            .room_password_match(&name, &RoomType::Float, password)
+            .await
+    {
+        return Err(ApiError::unauthorized("Password does not match"));
+    }
+
+    let mut values = match values {
+        PushedValues::Single(value) => vec![TimedValue {
+            value,
+            timestamp: None,
+        }],
+        PushedValues::Timed(value) => vec![value],
+        PushedValues::Batch(values) => values,
+    };
+    if values.len() > MAX_PUSHED_VALUES {
+        return Err(
+            anyhow::anyhow!("At most {MAX_PUSHED_VALUES} values can be pushed at once").into(),
+        );
+    }
+    values.sort_by_key(|value| value.timestamp);
+    let num_values = values.len();
+    if let (Some(first), Some(last)) = (
+        values.first().and_then(|value| value.timestamp),
+        values.last().and_then(|value| value.timestamp),
+    ) {
+        if Duration::from_millis(last - first) > MAX_PUSHED_SPAN {
+            return Err(anyhow::anyhow!(
+                "The timestamps of pushed values may span at most {} seconds",
+                MAX_PUSHED_SPAN.as_secs()
+            )
+            .into());
+        }
+    }
+
+    let sender = state.float_room_sender(&name, password).await?;
+    let span = info_span!("push_values", room_name = %name, num_values);
+    tokio::spawn(
+        async move {
+            let start = values.first().and_then(|value| value.timestamp);
+            let mut offset = 0;
+            for value in values {
+                if let (Some(start), Some(timestamp)) = (start, value.timestamp) {
+                    let value_offset = timestamp - start;
+                    sleep(Duration::from_millis(value_offset - offset)).await;
+                    offset = value_offset;
+                }
+                let _ = sender.publish(SteckerData::F32(value.value));
+            }
+        }
+        .instrument(span),
+    );
+
+    Ok(Json(PushValuesReply { values: num_values }))
+}
+
+/// the settings of a dispatcher, its admin password is only
+/// returned on creation
+#[derive(Serialize)]
+struct DispatcherReply {
+    #[serde(flatten)]
+    settings: RoomDispatcherInput,
+    queue_length: usize,
+    /// seconds until the dispatcher gets deleted unless it gets used
+    remaining_lifetime: u64,
+}
+
+impl DispatcherReply {
+    async fn new(dispatcher: &RoomDispatcher, admin_password: Option<String>) -> Self {
+        let mut settings = RoomDispatcherInput::from(dispatcher);
+        settings.admin_password = admin_password;
+        settings.admin_password_hash = None;
+        Self {
+            settings,
+            queue_length: dispatcher.num_waiting().await,
+            remaining_lifetime: dispatcher.remaining_lifetime().as_secs(),
+        }
+    }
+}
+
+async fn list_dispatchers(State(state): State<Arc<AppState>>) -> ApiResult<Vec<DispatcherReply>> {
+    let dispatchers: Vec<_> = state
+        .room_dispatchers
+        .read()
+        .await
+        .values()
+        .cloned()
+        .collect();
+    let mut replies = Vec::with_capacity(dispatchers.len());
+    for dispatcher in &dispatchers {
+        replies.push(DispatcherReply::new(dispatcher, None).await);
+    }
+    Ok(Json(replies))
+}
+
+async fn get_dispatcher(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> ApiResult<DispatcherReply> {
+    let dispatcher = state
+        .room_dispatchers
+        .read()
+        .await
+        .get(&name)
+        .cloned()
+        .ok_or_else(|| ApiError::not_found(format!("No such dispatcher {name}")))?;
+    Ok(Json(DispatcherReply::new(&dispatcher, None).await))
+}
+
+async fn create_dispatcher(
+    State(state): State<Arc<AppState>>,
+    Json(mut dispatcher): Json<RoomDispatcherInput>,
+) -> ApiResult<DispatcherReply> {
+    // only a hash of the password is kept, so a generated one gets returned from here
+    let admin_password = dispatcher
+        .admin_password
+        .get_or_insert_with(|| Alphanumeric.sample_string(&mut rand::thread_rng(), 8))
+        .clone();
+    dispatcher.admin_password_hash = None;
+    let dispatcher = state.create_dispatcher(dispatcher).await?;
+    Ok(Json(
+        DispatcherReply::new(&dispatcher, Some(admin_password)).await,
+    ))
+}
+
+#[derive(Deserialize)]
+struct UpdateDispatcherRequest {
+    admin_password: String,
+    #[serde(flatten)]
+    update: RoomDispatcherUpdate,
+}
+
+async fn update_dispatcher(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(request): Json<UpdateDispatcherRequest>,
+) -> ApiResult<DispatcherReply> {
+    if !state.room_dispatchers.read().await.contains_key(&name) {
+        return Err(ApiError::not_found(format!("No such dispatcher {name}")));
+    }
+    let dispatcher = state
+        .update_dispatcher(&name, &request.admin_password, request.update)
+        .await?;
+    Ok(Json(DispatcherReply::new(&dispatcher, None).await))
+}
+
+async fn access_dispatcher(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> ApiResult<Room> {
+    if !state.room_dispatchers.read().await.contains_key(&name) {
+        return Err(ApiError::not_found(format!("No such dispatcher {name}")));
+    }
+    Ok(Json(state.access_dispatcher(&name).await?))
+}
@@ -0,0 +1,123 @@
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use axum::{
+    extract::{self, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use crate::state::AppState;
+
+/// templates which are compiled into the binary
+const TEMPLATES: &[(&str, &str)] = &[
+    ("base.html.jinja", include_str!("templates/base.html.jinja")),
+    (
+        "debug.html.jinja",
+        include_str!("templates/debug.html.jinja"),
+    ),
+    (
+        "stream.html.jinja",
+        include_str!("templates/stream.html.jinja"),
+    ),
+    (
+        "dispatcher_not_found.html.jinja",
+        include_str!("templates/dispatcher_not_found.html.jinja"),
+    ),
+    (
+        "dispatcher_no_room_available.html.jinja",
+        include_str!("templates/dispatcher_no_room_available.html.jinja"),
+    ),
+];
+
+/// static files which are compiled into the binary
+const STATIC_FILES: &[(&str, &str)] = &[
+    ("stecker.js", include_str!("static/stecker.js")),
+    ("style.css", include_str!("static/style.css")),
+];
+
+/// only allows plain relative paths so a request can not escape the override directory
+fn override_path(override_dir: Option<&Path>, sub_dir: &str, name: &str) -> Option<PathBuf> {
+    let name = Path::new(name);
+    if !name
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+    override_dir.map(|dir| dir.join(sub_dir).join(name))
+}
+
+fn content_type(name: &str) -> &'static str {
+    match Path::new(name).extension().and_then(|ext| ext.to_str()) {
+        Some("js") => "text/javascript",
+        Some("css") => "text/css",
+        Some("html") => "text/html",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("ico") => "image/x-icon",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+/// creates the template environment - templates within the `templates` folder
+/// of the override directory take precedence over the embedded ones
+pub fn template_environment(override_dir: Option<PathBuf>) -> minijinja::Environment<'static> {
+    let mut env = minijinja::Environment::new();
+    env.set_loader(move |name| {
+        if let Some(path) = override_path(override_dir.as_deref(), "templates", name) {
+            match std::fs::read_to_string(&path) {
+                Ok(source) => return Ok(Some(source)),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => {
+                    return Err(minijinja::Error::new(
+                        minijinja::ErrorKind::InvalidOperation,
+                        "could not read template from override directory",
+                    )
+                    .with_source(err))
+                }
+            }
+        }
+        Ok(TEMPLATES
+            .iter()
+            .find(|(template_name, _)| *template_name == name)
+            .map(|(_, source)| source.to_string()))
+    });
+    env
+}
+
+/// serves files within the `static` folder of the override directory
+/// and falls back to the embedded static files
+pub async fn static_view(
+    State(state): State<Arc<AppState>>,
+    extract::Path(name): extract::Path<String>,
+) -> Response {
+    if let Some(path) = override_path(state.config.paths.overrides.as_deref(), "static", &name) {
+        match tokio::fs::read(&path).await {
+            Ok(content) => {
+                return ([(header::CONTENT_TYPE, content_type(&name))], content).into_response()
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => warn!(
+                ?err,
+                ?path,
+                "Could not read static file from override directory"
+            ),
+        }
+    }
+    match STATIC_FILES
+        .iter()
+        .find(|(file_name, _)| *file_name == name)
+    {
+        Some((_, content)) => {
+            ([(header::CONTENT_TYPE, content_type(&name))], *content).into_response()
+        }
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer};
+use tracing_subscriber::EnvFilter;
+use webrtc::ice_transport::ice_server::RTCIceServer;
+
+use crate::state::RoomTimeouts;
+
+const LOCAL_HOST: &str = "127.0.0.1";
+
+/// Configuration of the server, read from a TOML file.
+/// All values are optional and fall back to their defaults.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub network: NetworkConfig,
+    pub webrtc: WebRTCConfig,
+    pub limits: LimitsConfig,
+    pub logging: LoggingConfig,
+    pub telemetry: TelemetryConfig,
+    pub admin: AdminConfig,
+    pub persistence: PersistenceConfig,
+    pub paths: PathsConfig,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// interface to listen on
+    pub host: String,
+    /// http port to listen on
+    pub port: u16,
+    pub osc_host: String,
+    /// tcp osc port to listen on
+    pub osc_port: u16,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            host: LOCAL_HOST.to_string(),
+            port: 8000,
+            osc_host: LOCAL_HOST.to_string(),
+            osc_port: 1337,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebRTCConfig {
+    /// STUN/TURN servers, e.g. `{urls = ["turn:turn.example.com:3478"], username = "...", credential = "..."}`,
+    /// servers without credentials can also be given as url, e.g. `"stun:stun.l.google.com:19302"`
+    #[serde(deserialize_with = "deserialize_ice_servers")]
+    pub ice_servers: Vec<IceServerConfig>,
+}
+
+impl Default for WebRTCConfig {
+    fn default() -> Self {
+        Self {
+            ice_servers: vec![shared::connections::DEFAULT_ICE_SERVER.into()],
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct IceServerConfig {
+    pub urls: Vec<String>,
+    /// required by TURN servers
+    pub username: Option<String>,
+    pub credential: Option<String>,
+}
+
+/// an ICE server without credentials can be given as url
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IceServerEntry {
+    Url(String),
+    Server(IceServerConfig),
+}
+
+impl From<&str> for IceServerConfig {
+    fn from(url: &str) -> Self {
+        Self {
+            urls: vec![url.to_string()],
+            username: None,
+            credential: None,
+        }
+    }
+}
+
+fn deserialize_ice_servers<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<IceServerConfig>, D::Error> {
+    Ok(Vec::<IceServerEntry>::deserialize(deserializer)?
+        .into_iter()
+        .map(|entry| match entry {
+            IceServerEntry::Url(url) => url.as_str().into(),
+            IceServerEntry::Server(server) => server,
+        })
+        .collect())
+}
+
+impl From<&IceServerConfig> for RTCIceServer {
+    fn from(server: &IceServerConfig) -> Self {
+        RTCIceServer {
+            urls: server.urls.clone(),
+            username: server.username.clone().unwrap_or_default(),
+            credential: server.credential.clone().unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+/// timeouts are in seconds
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    pub audio_inactivity_timeout: u64,
+    pub float_inactivity_timeout: u64,
+    pub chat_inactivity_timeout: u64,
+    pub max_room_lifetime: Option<u64>,
+    /// number of room events which are buffered for slow consumers
+    pub room_events_capacity: usize,
+    /// number of OSC messages which are buffered per OSC connection
+    pub osc_queue_capacity: usize,
+    /// time between announcing a shutdown and closing all connections
+    pub shutdown_drain_period: u64,
+    /// number of messages a chat room keeps for the `chatMessages` query
+    pub chat_history_length: usize,
+    /// number of recent messages of a chat room which get replayed to new listeners,
+    /// unless the creator of the room chooses otherwise
+    pub chat_replay_length: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        let room_timeouts = RoomTimeouts::default();
+        Self {
+            audio_inactivity_timeout: room_timeouts.audio_inactivity.as_secs(),
+            float_inactivity_timeout: room_timeouts.float_inactivity.as_secs(),
+            chat_inactivity_timeout: room_timeouts.chat_inactivity.as_secs(),
+            max_room_lifetime: None,
+            room_events_capacity: 32,
+            osc_queue_capacity: 16,
+            shutdown_drain_period: 5,
+            chat_history_length: 50,
+            chat_replay_length: 10,
+        }
+    }
+}
+
+impl LimitsConfig {
+    pub fn room_timeouts(&self) -> RoomTimeouts {
+        RoomTimeouts {
+            audio_inactivity: Duration::from_secs(self.audio_inactivity_timeout),
+            float_inactivity: Duration::from_secs(self.float_inactivity_timeout),
+            chat_inactivity: Duration::from_secs(self.chat_inactivity_timeout),
+            max_lifetime: self.max_room_lifetime.map(Duration::from_secs),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// human readable lines
+    #[default]
+    Pretty,
+    /// one JSON object per line
+    Json,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// `RUST_LOG`-style filter, e.g. `error,server=info,shared=warn`
+    pub filter: String,
+    pub format: LogFormat,
+    /// deprecated, replaced by `filter` - level of all dependencies
+    default_level: Option<String>,
+    /// deprecated, replaced by `filter`
+    server_level: Option<String>,
+    /// deprecated, replaced by `filter`
+    shared_level: Option<String>,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            filter: "error,server=trace,shared=trace".to_string(),
+            format: LogFormat::Pretty,
+            default_level: None,
+            server_level: None,
+            shared_level: None,
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// turns the levels of older configuration files into a filter
+    fn apply_legacy_levels(&mut self) {
+        if self.default_level.is_none()
+            && self.server_level.is_none()
+            && self.shared_level.is_none()
+        {
+            return;
+        }
+        self.filter = format!(
+            "{},server={},shared={}",
+            self.default_level.as_deref().unwrap_or("error"),
+            self.server_level.as_deref().unwrap_or("trace"),
+            self.shared_level.as_deref().unwrap_or("trace"),
+        );
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    /// spans are exported via OTLP/gRPC to this collector, e.g. `http://localhost:4317`
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: "stecker_server".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    /// password for administrative GraphQL operations - these are disabled if not set
+    pub password: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct PersistenceConfig {
+    /// dispatchers get stored in this JSON file and are restored on startup
+    pub dispatchers_file: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    /// directory with a `templates` and/or `static` folder whose files
+    /// take precedence over the ones embedded in the binary
+    pub overrides: Option<PathBuf>,
+}
+
+impl Config {
+    /// reads the configuration from a TOML file or uses the defaults if no path is given
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        match path {
+            Some(path) => {
+                let content = fs::read_to_string(path)
+                    .with_context(|| format!("Could not read config file {path:?}"))?;
+                let mut config: Self = toml::from_str(&content)
+                    .with_context(|| format!("Could not parse config file {path:?}"))?;
+                config.logging.apply_legacy_levels();
+                Ok(config)
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.network.host == self.network.osc_host && self.network.port == self.network.osc_port
+        {
+            bail!("network.port and network.osc_port must not be the same");
+        }
+        if self.webrtc.ice_servers.is_empty() {
+            bail!("webrtc.ice_servers needs at least one server");
+        }
+        for server in &self.webrtc.ice_servers {
+            if server.urls.is_empty() {
+                bail!("Every ICE server needs at least one url");
+            }
+            for url in &server.urls {
+                if !["stun:", "stuns:", "turn:", "turns:"]
+                    .iter()
+                    .any(|scheme| url.starts_with(scheme))
+                {
+                    bail!("Invalid ICE server url \"{url}\" - needs to start with stun: or turn:");
+                }
+                if url.starts_with("turn")
+                    && (server.username.is_none() || server.credential.is_none())
+                {
+                    bail!("TURN server \"{url}\" needs a username and a credential");
+                }
+            }
+        }
+        for (name, timeout) in [
+            (
+                "audio_inactivity_timeout",
+                self.limits.audio_inactivity_timeout,
+            ),
+            (
+                "float_inactivity_timeout",
+                self.limits.float_inactivity_timeout,
+            ),
+            (
+                "chat_inactivity_timeout",
+                self.limits.chat_inactivity_timeout,
+            ),
+        ] {
+            if timeout == 0 {
+                bail!("limits.{name} must be greater than 0");
+            }
+        }
+        if self.limits.room_events_capacity == 0 || self.limits.osc_queue_capacity == 0 {
+            bail!(
+                "limits.room_events_capacity and limits.osc_queue_capacity must be greater than 0"
+            );
+        }
+        EnvFilter::try_new(&self.logging.filter)
+            .with_context(|| format!("Invalid log filter \"{}\"", self.logging.filter))?;
+        if let Some(endpoint) = &self.telemetry.otlp_endpoint {
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                bail!("Invalid OTLP endpoint \"{endpoint}\" - needs to start with http:// or https://");
+            }
+        }
+        if self.admin.password.as_deref() == Some("") {
+            bail!("admin.password must not be empty");
+        }
+        if let Some(overrides) = &self.paths.overrides {
+            if !overrides.is_dir() {
+                bail!("Override directory {overrides:?} does not exist");
+            }
+        }
+        Ok(())
+    }
+}
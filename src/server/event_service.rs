@@ -1,25 +1,125 @@
 use rosc::{OscMessage, OscPacket};
+use serde_json::{json, Value};
+
+use crate::models::RoomType;
 
 #[derive(Debug, Clone)]
 pub enum RoomEvent {
-    BroadcastRoomCreated(String),
-    BroadcastRoomUpdated(String),
-    BroadcastRoomUserCount(String, i32),
-    BroadcastRoomDeleted(String),
+    BroadcastRoomCreated(RoomType, String),
+    BroadcastRoomUpdated(RoomType, String),
+    BroadcastRoomUserCount(RoomType, String, i32),
+    BroadcastRoomDeleted(RoomType, String),
+    /// short message of a listener to the sender of the room
+    BroadcastRoomFeedback(RoomType, String, String),
+    /// source room and the return room reserved for a dispatched listener
+    ReturnRoomReserved(String, String),
 
     RoomDispatcherCreated(String),
+    RoomDispatcherUpdated(String),
     RoomDispatcherDeleted(String),
     RoomDispatcherReset(),
+    /// number of visitors waiting for a free room
+    RoomDispatcherQueue(String, i32),
+
+    /// seconds until all connections get closed
+    ServerShutdown(i32),
 }
 
 impl RoomEvent {
+    /// camelCase name of the event, used as SSE event type
+    pub fn name(&self) -> &'static str {
+        match self {
+            RoomEvent::BroadcastRoomCreated(..) => "roomCreated",
+            RoomEvent::BroadcastRoomUpdated(..) => "roomUpdated",
+            RoomEvent::BroadcastRoomUserCount(..) => "roomUserCount",
+            RoomEvent::BroadcastRoomDeleted(..) => "roomDeleted",
+            RoomEvent::BroadcastRoomFeedback(..) => "roomFeedback",
+            RoomEvent::ReturnRoomReserved(..) => "returnRoomReserved",
+            RoomEvent::RoomDispatcherCreated(_) => "dispatcherCreated",
+            RoomEvent::RoomDispatcherUpdated(_) => "dispatcherUpdated",
+            RoomEvent::RoomDispatcherDeleted(_) => "dispatcherDeleted",
+            RoomEvent::RoomDispatcherReset() => "dispatcherReset",
+            RoomEvent::RoomDispatcherQueue(..) => "dispatcherQueue",
+            RoomEvent::ServerShutdown(_) => "serverShutdown",
+        }
+    }
+
+    /// type of the room the event is about, `None` for non-room events
+    pub fn room_type(&self) -> Option<RoomType> {
+        match self {
+            RoomEvent::BroadcastRoomCreated(room_type, _)
+            | RoomEvent::BroadcastRoomUpdated(room_type, _)
+            | RoomEvent::BroadcastRoomUserCount(room_type, _, _)
+            | RoomEvent::BroadcastRoomDeleted(room_type, _)
+            | RoomEvent::BroadcastRoomFeedback(room_type, _, _) => Some(*room_type),
+            _ => None,
+        }
+    }
+
+    /// name of the room or dispatcher the event is about
+    pub fn subject(&self) -> Option<&str> {
+        match self {
+            RoomEvent::BroadcastRoomCreated(_, name)
+            | RoomEvent::BroadcastRoomUpdated(_, name)
+            | RoomEvent::BroadcastRoomUserCount(_, name, _)
+            | RoomEvent::BroadcastRoomDeleted(_, name)
+            | RoomEvent::BroadcastRoomFeedback(_, name, _)
+            | RoomEvent::ReturnRoomReserved(name, _)
+            | RoomEvent::RoomDispatcherCreated(name)
+            | RoomEvent::RoomDispatcherUpdated(name)
+            | RoomEvent::RoomDispatcherDeleted(name)
+            | RoomEvent::RoomDispatcherQueue(name, _) => Some(name),
+            RoomEvent::RoomDispatcherReset() | RoomEvent::ServerShutdown(_) => None,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let event = self.name();
+        match self {
+            RoomEvent::BroadcastRoomCreated(room_type, room)
+            | RoomEvent::BroadcastRoomUpdated(room_type, room)
+            | RoomEvent::BroadcastRoomDeleted(room_type, room) => {
+                json!({"event": event, "room": room, "roomType": room_type})
+            }
+            RoomEvent::BroadcastRoomUserCount(room_type, room, num_listeners) => json!({
+                "event": event,
+                "room": room,
+                "roomType": room_type,
+                "numListeners": num_listeners,
+            }),
+            RoomEvent::BroadcastRoomFeedback(room_type, room, message) => json!({
+                "event": event,
+                "room": room,
+                "roomType": room_type,
+                "message": message,
+            }),
+            RoomEvent::ReturnRoomReserved(room, return_room) => {
+                json!({"event": event, "room": room, "returnRoom": return_room})
+            }
+            RoomEvent::RoomDispatcherCreated(dispatcher)
+            | RoomEvent::RoomDispatcherUpdated(dispatcher)
+            | RoomEvent::RoomDispatcherDeleted(dispatcher) => {
+                json!({"event": event, "dispatcher": dispatcher})
+            }
+            RoomEvent::RoomDispatcherReset() => json!({"event": event}),
+            RoomEvent::RoomDispatcherQueue(dispatcher, queue_length) => json!({
+                "event": event,
+                "dispatcher": dispatcher,
+                "queueLength": queue_length,
+            }),
+            RoomEvent::ServerShutdown(drain_period) => {
+                json!({"event": event, "drainPeriod": drain_period})
+            }
+        }
+    }
+
     pub async fn into_osc_packet(self) -> OscPacket {
         match self {
-            RoomEvent::BroadcastRoomCreated(room_name) => OscPacket::Message(OscMessage {
+            RoomEvent::BroadcastRoomCreated(_, room_name) => OscPacket::Message(OscMessage {
                 addr: "/createdRoom".to_string(),
                 args: vec![rosc::OscType::String(room_name)],
             }),
-            RoomEvent::BroadcastRoomUpdated(room_name) => {
+            RoomEvent::BroadcastRoomUpdated(_, room_name) => {
                 let updated = rosc::OscType::String("updated".to_string());
                 let room = rosc::OscType::String(room_name);
                 OscPacket::Message(OscMessage {
@@ -27,7 +127,7 @@ impl RoomEvent {
                     args: vec![room.clone()],
                 })
             }
-            RoomEvent::BroadcastRoomDeleted(room_name) => {
+            RoomEvent::BroadcastRoomDeleted(_, room_name) => {
                 let deleted = rosc::OscType::String("deleted".to_string());
                 let room = rosc::OscType::String(room_name);
                 OscPacket::Message(OscMessage {
@@ -35,7 +135,7 @@ impl RoomEvent {
                     args: vec![room.clone()],
                 })
             }
-            RoomEvent::BroadcastRoomUserCount(room_name, new_num_listeners) => {
+            RoomEvent::BroadcastRoomUserCount(_, room_name, new_num_listeners) => {
                 let room_joined = rosc::OscType::String("users".to_string());
                 OscPacket::Message(OscMessage {
                     addr: "/room".to_string(),
@@ -46,11 +146,35 @@ impl RoomEvent {
                     ],
                 })
             }
+            RoomEvent::BroadcastRoomFeedback(_, room_name, message) => {
+                OscPacket::Message(OscMessage {
+                    addr: "/room".to_string(),
+                    args: vec![
+                        rosc::OscType::String(room_name),
+                        rosc::OscType::String("feedback".to_string()),
+                        rosc::OscType::String(message),
+                    ],
+                })
+            }
+            RoomEvent::ReturnRoomReserved(room_name, return_room_name) => {
+                OscPacket::Message(OscMessage {
+                    addr: "/room".to_string(),
+                    args: vec![
+                        rosc::OscType::String(room_name),
+                        rosc::OscType::String("returnRoom".to_string()),
+                        rosc::OscType::String(return_room_name),
+                    ],
+                })
+            }
 
             RoomEvent::RoomDispatcherCreated(name) => OscPacket::Message(OscMessage {
                 addr: "/createdDispatcher".to_string(),
                 args: vec![rosc::OscType::String(name)],
             }),
+            RoomEvent::RoomDispatcherUpdated(name) => OscPacket::Message(OscMessage {
+                addr: "/updatedDispatcher".to_string(),
+                args: vec![rosc::OscType::String(name)],
+            }),
             RoomEvent::RoomDispatcherDeleted(name) => OscPacket::Message(OscMessage {
                 addr: "/deletedDispatcher".to_string(),
                 args: vec![rosc::OscType::String(name)],
@@ -59,6 +183,18 @@ impl RoomEvent {
                 addr: "/resetDispatcher".to_string(),
                 args: vec![],
             }),
+            RoomEvent::RoomDispatcherQueue(name, queue_length) => OscPacket::Message(OscMessage {
+                addr: "/dispatcherQueue".to_string(),
+                args: vec![
+                    rosc::OscType::String(name),
+                    rosc::OscType::Int(queue_length),
+                ],
+            }),
+
+            RoomEvent::ServerShutdown(drain_period) => OscPacket::Message(OscMessage {
+                addr: "/serverShutdown".to_string(),
+                args: vec![rosc::OscType::Int(drain_period)],
+            }),
         }
     }
 }
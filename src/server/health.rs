@@ -0,0 +1,135 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use shared::connections::SteckerWebRTCConnection;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::{state::AppState, views::Template};
+
+/// how long the result of the WebRTC check gets reused, as building
+/// a peer connection on every probe is expensive
+const WEBRTC_CHECK_TTL: Duration = Duration::from_secs(60);
+
+/// set by the server once its listeners are bound
+#[derive(Debug, Default)]
+pub struct Readiness {
+    pub http_bound: AtomicBool,
+    pub osc_bound: AtomicBool,
+    /// time and outcome of the last WebRTC check
+    webrtc_check: Mutex<Option<(Instant, Result<(), String>)>>,
+}
+
+#[derive(Serialize)]
+pub struct Check {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl From<anyhow::Result<()>> for Check {
+    fn from(value: anyhow::Result<()>) -> Self {
+        match value {
+            Ok(()) => Check {
+                ok: true,
+                error: None,
+            },
+            Err(err) => Check {
+                ok: false,
+                error: Some(format!("{err:#}")),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct HealthReport {
+    status: &'static str,
+    version: &'static str,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    checks: BTreeMap<&'static str, Check>,
+}
+
+fn flag_check(flag: &AtomicBool, error: &str) -> anyhow::Result<()> {
+    if flag.load(Ordering::Relaxed) {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(error.to_string()))
+    }
+}
+
+async fn webrtc_check(readiness: &Readiness) -> anyhow::Result<()> {
+    // concurrent probes wait for the running check instead of starting their own
+    let mut last_check = readiness.webrtc_check.lock().await;
+    let result = match &*last_check {
+        Some((checked_at, result)) if checked_at.elapsed() < WEBRTC_CHECK_TTL => result.clone(),
+        _ => {
+            let result = async {
+                let connection = SteckerWebRTCConnection::build_connection().await?;
+                connection.close().await
+            }
+            .await
+            .map_err(|err| format!("{err:#}"));
+            *last_check = Some((Instant::now(), result.clone()));
+            result
+        }
+    };
+    result.map_err(|err| anyhow::anyhow!(err))
+}
+
+fn templates_check(state: &AppState) -> anyhow::Result<()> {
+    for template in Template::ALL {
+        state.jinja.get_template(template.as_str())?;
+    }
+    Ok(())
+}
+
+/// the process is alive
+pub async fn healthz_view() -> Json<HealthReport> {
+    Json(HealthReport {
+        status: "ok",
+        version: env!("CARGO_PKG_VERSION"),
+        checks: BTreeMap::new(),
+    })
+}
+
+/// the server is able to serve rooms
+pub async fn readyz_view(State(state): State<Arc<AppState>>) -> (StatusCode, Json<HealthReport>) {
+    let mut checks = BTreeMap::new();
+    checks.insert(
+        "http",
+        flag_check(&state.readiness.http_bound, "HTTP listener is not bound").into(),
+    );
+    checks.insert(
+        "osc",
+        flag_check(&state.readiness.osc_bound, "OSC listener is not bound").into(),
+    );
+    checks.insert("webrtc", webrtc_check(&state.readiness).await.into());
+    checks.insert("templates", templates_check(&state).into());
+    checks.insert("shutdown", state.shutdown.ensure_running().into());
+
+    let ready = checks.values().all(|check: &Check| check.ok);
+    if !ready {
+        warn!("Server is not ready");
+    }
+    (
+        if ready {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        },
+        Json(HealthReport {
+            status: if ready { "ok" } else { "unavailable" },
+            version: env!("CARGO_PKG_VERSION"),
+            checks,
+        }),
+    )
+}
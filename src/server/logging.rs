@@ -0,0 +1,67 @@
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, Context};
+use tracing::info;
+use tracing_subscriber::{
+    layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Layer, Registry,
+};
+
+use crate::{
+    config::{LogFormat, LoggingConfig, TelemetryConfig},
+    telemetry,
+};
+
+type FilterHandle = reload::Handle<EnvFilter, Registry>;
+
+static FILTER_HANDLE: OnceLock<FilterHandle> = OnceLock::new();
+
+/// installs the global subscriber whose filter can be replaced at runtime
+/// and which exports its spans via OTLP if configured
+pub fn init(config: &LoggingConfig, telemetry_config: &TelemetryConfig) -> anyhow::Result<()> {
+    let filter = EnvFilter::try_new(&config.filter)
+        .with_context(|| format!("Invalid log filter \"{}\"", config.filter))?;
+    let (filter_layer, handle) = reload::Layer::new(filter);
+
+    let fmt_layer = match config.format {
+        LogFormat::Pretty => tracing_subscriber::fmt::layer().boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+    };
+
+    let otel_layer = telemetry::init_tracer(telemetry_config)?
+        .map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer));
+
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(fmt_layer)
+        .with(otel_layer)
+        .try_init()?;
+
+    FILTER_HANDLE
+        .set(handle)
+        .map_err(|_| anyhow!("Logging has already been initialized"))
+}
+
+fn handle() -> anyhow::Result<&'static FilterHandle> {
+    FILTER_HANDLE
+        .get()
+        .ok_or_else(|| anyhow!("Logging has not been initialized"))
+}
+
+/// the currently active `RUST_LOG`-style filter
+pub fn current_filter() -> anyhow::Result<String> {
+    Ok(handle()?.with_current(|filter| filter.to_string())?)
+}
+
+/// replaces the active filter and returns the new one
+pub fn set_filter(filter: &str) -> anyhow::Result<String> {
+    let new_filter =
+        EnvFilter::try_new(filter).with_context(|| format!("Invalid log filter \"{filter}\""))?;
+    handle()?.reload(new_filter)?;
+    let filter = current_filter()?;
+    info!(filter, "Changed log filter");
+    Ok(filter)
+}
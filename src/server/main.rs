@@ -1,52 +1,179 @@
+pub mod api;
+pub mod assets;
+pub mod config;
 pub mod event_service;
+pub mod health;
+pub mod logging;
+pub mod metrics_service;
 pub mod models;
 pub mod osc_listener;
+pub mod persistence;
 pub mod schema;
+pub mod shutdown;
 pub mod state;
+pub mod telemetry;
 pub mod views;
+pub mod webhook_service;
 
-use std::sync::Arc;
+use std::{
+    path::PathBuf,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
 
+use crate::config::{Config, LogFormat};
 use crate::schema::{Mutation, Query};
 
+use assets::static_view;
 use async_graphql::extensions::Tracing;
 use async_graphql::{http::GraphiQLSource, EmptySubscription, Schema};
 use async_graphql_axum::GraphQL;
 use axum::{
+    middleware,
     response::{self, IntoResponse},
     routing::get,
     Router,
 };
 use clap::Parser;
+use health::{healthz_view, readyz_view};
+use metrics_service::metrics_view;
 use osc_listener::handle_osc_client;
+use persistence::{persist_dispatchers, restore_dispatchers};
 use state::AppState;
+use telemetry::trace_context;
 use tokio::net::TcpListener;
-use tower_http::services::ServeDir;
-use tracing::{debug, error, info, Level};
-use tracing_subscriber::layer::SubscriberExt;
-use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::{self, filter};
-use views::{debug_view, dispatcher_view, stream_view};
-
-const LOCAL_HOST: &str = "127.0.0.1";
+use tracing::{debug, error, info};
+use views::{debug_view, dispatcher_queue_view, dispatcher_view, events_view, stream_view};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
+    /// path to a TOML configuration file - options given on the command line
+    /// or via environment variables take precedence over the file
+    #[arg(long, env = "STECKER_CONFIG")]
+    config: Option<PathBuf>,
+
     /// interface to listen on
-    #[arg(long, default_value_t=LOCAL_HOST.to_string())]
-    host: String,
+    #[arg(long, env = "STECKER_HOST")]
+    host: Option<String>,
 
     /// http port to listen on
-    #[arg(long, default_value_t = 8000)]
-    port: u16,
+    #[arg(long, env = "STECKER_PORT")]
+    port: Option<u16>,
 
-    #[arg(long, default_value_t=LOCAL_HOST.to_string())]
-    osc_host: String,
+    #[arg(long, env = "STECKER_OSC_HOST")]
+    osc_host: Option<String>,
 
     /// tcp osc port to listen on
-    #[arg(long, default_value_t = 1337)]
-    osc_port: u16,
+    #[arg(long, env = "STECKER_OSC_PORT")]
+    osc_port: Option<u16>,
+
+    /// seconds without audio from the sender after which an audio room gets deleted
+    #[arg(long, env = "STECKER_AUDIO_INACTIVITY_TIMEOUT")]
+    audio_inactivity_timeout: Option<u64>,
+
+    /// seconds without a value from the sender after which a float room gets deleted
+    #[arg(long, env = "STECKER_FLOAT_INACTIVITY_TIMEOUT")]
+    float_inactivity_timeout: Option<u64>,
+
+    /// seconds without a message from the sender after which a chat room gets deleted
+    #[arg(long, env = "STECKER_CHAT_INACTIVITY_TIMEOUT")]
+    chat_inactivity_timeout: Option<u64>,
+
+    /// maximum lifetime of any room in seconds
+    #[arg(long, env = "STECKER_MAX_ROOM_LIFETIME")]
+    max_room_lifetime: Option<u64>,
+
+    /// `RUST_LOG`-style log filter, e.g. `error,server=info`
+    #[arg(long, env = "RUST_LOG")]
+    log_filter: Option<String>,
+
+    #[arg(long, env = "STECKER_LOG_FORMAT")]
+    log_format: Option<LogFormat>,
+
+    /// OTLP/gRPC collector to export spans to, e.g. `http://localhost:4317`
+    #[arg(long, env = "OTEL_EXPORTER_OTLP_ENDPOINT")]
+    otlp_endpoint: Option<String>,
+
+    /// password for administrative GraphQL operations
+    #[arg(long, env = "STECKER_ADMIN_PASSWORD")]
+    admin_password: Option<String>,
+
+    /// seconds between announcing a shutdown and closing all connections
+    #[arg(long, env = "STECKER_SHUTDOWN_DRAIN_PERIOD")]
+    shutdown_drain_period: Option<u64>,
+}
+
+impl Cli {
+    fn config(&self) -> anyhow::Result<Config> {
+        let mut config = Config::load(self.config.as_deref())?;
+        if let Some(host) = &self.host {
+            config.network.host = host.clone();
+        }
+        if let Some(port) = self.port {
+            config.network.port = port;
+        }
+        if let Some(osc_host) = &self.osc_host {
+            config.network.osc_host = osc_host.clone();
+        }
+        if let Some(osc_port) = self.osc_port {
+            config.network.osc_port = osc_port;
+        }
+        if let Some(timeout) = self.audio_inactivity_timeout {
+            config.limits.audio_inactivity_timeout = timeout;
+        }
+        if let Some(timeout) = self.float_inactivity_timeout {
+            config.limits.float_inactivity_timeout = timeout;
+        }
+        if let Some(timeout) = self.chat_inactivity_timeout {
+            config.limits.chat_inactivity_timeout = timeout;
+        }
+        if self.max_room_lifetime.is_some() {
+            config.limits.max_room_lifetime = self.max_room_lifetime;
+        }
+        if let Some(log_filter) = &self.log_filter {
+            config.logging.filter = log_filter.clone();
+        }
+        if let Some(log_format) = self.log_format {
+            config.logging.format = log_format;
+        }
+        if self.otlp_endpoint.is_some() {
+            config.telemetry.otlp_endpoint = self.otlp_endpoint.clone();
+        }
+        if self.admin_password.is_some() {
+            config.admin.password = self.admin_password.clone();
+        }
+        if let Some(drain_period) = self.shutdown_drain_period {
+            config.limits.shutdown_drain_period = drain_period;
+        }
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// resolves on Ctrl-C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl-C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
 
 async fn graphiql() -> impl IntoResponse {
@@ -55,21 +182,43 @@ async fn graphiql() -> impl IntoResponse {
 
 #[tokio::main]
 async fn main() {
-    let args = Cli::parse();
+    let config = match Cli::parse().config() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Invalid configuration: {err:#}");
+            std::process::exit(1);
+        }
+    };
+
+    logging::init(&config.logging, &config.telemetry).unwrap();
 
-    let filter = filter::Targets::new()
-        .with_default(Level::ERROR)
-        .with_target("server", Level::TRACE)
-        .with_target("shared", Level::TRACE);
+    metrics_service::install_recorder().unwrap();
+    shared::connections::set_ice_servers(
+        config.webrtc.ice_servers.iter().map(Into::into).collect(),
+    )
+    .unwrap();
 
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
-        .with(filter)
-        .init();
+    let network = config.network.clone();
+    let dispatchers_file = config.persistence.dispatchers_file.clone();
 
-    let app_state = Arc::new(AppState::new());
+    let app_state = Arc::new(AppState::new(config));
     let app_state2 = app_state.clone();
 
+    if let Some(path) = dispatchers_file {
+        match restore_dispatchers(&app_state, &path).await {
+            Ok(()) => {
+                let persist_state = app_state.clone();
+                tokio::spawn(async move { persist_dispatchers(persist_state, &path).await });
+            }
+            // the file does not get overwritten, so its dispatchers are not lost
+            Err(err) => {
+                error!("Failed to restore dispatchers - dispatchers will not be stored: {err:#}")
+            }
+        }
+    }
+
+    tokio::spawn(webhook_service::dispatch_webhooks(app_state.clone()));
+
     let schema = Schema::build(Query, Mutation, EmptySubscription)
         .data(app_state.clone())
         .extension(Tracing)
@@ -77,29 +226,52 @@ async fn main() {
 
     let http_app = Router::new()
         .route("/graphql", get(graphiql).post_service(GraphQL::new(schema)))
-        .nest_service("/static", ServeDir::new("static"))
+        .route("/static/*path", get(static_view))
+        .route("/metrics", get(metrics_view))
+        .route("/healthz", get(healthz_view))
+        .route("/readyz", get(readyz_view))
         .route("/debug", get(debug_view))
+        .route("/events", get(events_view))
         .route("/s/:name", get(stream_view))
         .route("/d/:name", get(dispatcher_view))
+        .route("/d/:name/queue", get(dispatcher_queue_view))
+        .nest("/api/v1", api::router())
+        .layer(middleware::from_fn(trace_context))
         .with_state(app_state.clone());
 
+    let http_network = network.clone();
+    let http_shutdown = app_state.shutdown.clone();
+    let http_state = app_state.clone();
     let http_handle = tokio::spawn(async move {
-        info!("Start http serving on http://{}:{}", args.host, args.port);
-        axum::serve(
-            TcpListener::bind((args.host, args.port)).await.unwrap(),
-            http_app,
-        )
-        .await
+        info!(
+            "Start http serving on http://{}:{}",
+            http_network.host, http_network.port
+        );
+        let http_listener = TcpListener::bind((http_network.host.as_str(), http_network.port))
+            .await
+            .unwrap();
+        http_state
+            .readiness
+            .http_bound
+            .store(true, Ordering::Relaxed);
+        axum::serve(http_listener, http_app)
+            .with_graceful_shutdown(async move { http_shutdown.closing().await })
+            .await
     });
 
     let osc_app = tokio::spawn(async move {
         info!(
             "Start TCP-OSC serving on {}:{}",
-            args.osc_host, args.osc_port
+            network.osc_host, network.osc_port
         );
-        let tcp_osc_listener = tokio::net::TcpListener::bind((args.osc_host, args.osc_port))
-            .await
-            .unwrap();
+        let tcp_osc_listener =
+            tokio::net::TcpListener::bind((network.osc_host.as_str(), network.osc_port))
+                .await
+                .unwrap();
+        app_state2
+            .readiness
+            .osc_bound
+            .store(true, Ordering::Relaxed);
         loop {
             match tcp_osc_listener.accept().await {
                 Ok((osc_socket, addr)) => {
@@ -114,5 +286,17 @@ async fn main() {
         }
     });
 
-    let _ = tokio::join!(http_handle, osc_app,);
+    shutdown_signal().await;
+    info!("Received shutdown signal");
+    app_state.shut_down().await;
+
+    osc_app.abort();
+    if tokio::time::timeout(Duration::from_secs(1), http_handle)
+        .await
+        .is_err()
+    {
+        debug!("Not all http connections have been finished");
+    }
+    info!("Server stopped");
+    telemetry::shutdown();
 }
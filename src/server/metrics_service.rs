@@ -0,0 +1,83 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, OnceLock,
+    },
+    time::Duration,
+};
+
+use axum::{extract::State, http::header, response::IntoResponse};
+use metrics::gauge;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use metrics_util::MetricKindMask;
+
+use crate::{models::RoomType, state::AppState};
+
+/// metrics of deleted rooms are dropped after this duration without an update
+const METRICS_IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+
+static PROMETHEUS: OnceLock<PrometheusHandle> = OnceLock::new();
+static OSC_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// installs the global metrics recorder, so metrics recorded by the server
+/// and the shared library can be exported via `/metrics`
+pub fn install_recorder() -> anyhow::Result<()> {
+    let handle = PrometheusBuilder::new()
+        .idle_timeout(
+            MetricKindMask::COUNTER | MetricKindMask::GAUGE,
+            Some(METRICS_IDLE_TIMEOUT),
+        )
+        .install_recorder()?;
+    PROMETHEUS
+        .set(handle)
+        .map_err(|_| anyhow::anyhow!("Metrics recorder has already been installed"))
+}
+
+/// counts an OSC connection for as long as the guard is alive
+pub struct OscConnectionGuard;
+
+impl OscConnectionGuard {
+    pub fn register() -> Self {
+        OSC_CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for OscConnectionGuard {
+    fn drop(&mut self) {
+        OSC_CONNECTIONS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// gauges which reflect the current state get updated on each scrape
+async fn update_state_gauges(state: &AppState) {
+    for room_type in [RoomType::Float, RoomType::Chat, RoomType::Audio] {
+        let rooms = state.get_rooms(&room_type).await;
+        gauge!("stecker_rooms", "type" => room_type.to_string()).set(rooms.len() as f64);
+        for room in rooms {
+            gauge!(
+                "stecker_room_listeners",
+                "type" => room_type.to_string(),
+                "room" => room.name,
+            )
+            .set(room.num_listeners as f64);
+        }
+    }
+    gauge!("stecker_dispatchers").set(state.room_dispatchers.read().await.len() as f64);
+    gauge!("stecker_osc_connections").set(OSC_CONNECTIONS.load(Ordering::Relaxed) as f64);
+}
+
+pub async fn metrics_view(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    update_state_gauges(&state).await;
+    let rendered = match PROMETHEUS.get() {
+        Some(handle) => {
+            handle.run_upkeep();
+            handle.render()
+        }
+        None => String::new(),
+    };
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        rendered,
+    )
+}
@@ -1,17 +1,31 @@
-use std::{fmt::Display, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Display,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-use async_graphql::{Enum, InputObject, Object, SimpleObject};
+use async_graphql::{
+    ComplexObject, Context, Enum, InputObject, MaybeUndefined, Object, SimpleObject,
+};
 use futures::stream::{self, StreamExt};
+use hmac::{Hmac, Mac};
+use metrics::counter;
 use rand::{
     distributions::{Alphanumeric, DistString},
     SeedableRng,
 };
 use rand::{rngs::StdRng, seq::SliceRandom};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 use shared::connections::ConnectionEvent;
 use shared::{
     connections::SteckerWebRTCConnection,
-    models::{DataRoomInternalType, SteckerAudioChannel, SteckerData},
+    models::{DataRoomInternalType, MetaMessage, SteckerAudioChannel, SteckerData},
 };
 use tokio::sync::broadcast::Sender;
 use tokio::sync::RwLock;
@@ -20,15 +34,38 @@ use uuid::Uuid;
 use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
 use webrtc::track::track_local::TrackLocalWriter;
 
-use crate::event_service::RoomEvent;
+use crate::{
+    event_service::RoomEvent,
+    shutdown::Shutdown,
+    state::{AppState, StateError},
+};
 
 // graphql objects
 
-#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub enum DispatcherType {
     Random,
     NextFreeAlphabetical,
     NextFreeRandom,
+    /// room with the fewest listeners
+    LeastListeners,
+    /// cycles through the matched rooms in alphabetical order
+    RoundRobin,
+    /// next room (alphabetical) which has less than `max_listeners` listeners
+    MaxListeners,
+}
+
+impl TryFrom<String> for RoomType {
+    type Error = ();
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_ascii_lowercase().as_str() {
+            "float" => Ok(RoomType::Float),
+            "chat" => Ok(RoomType::Chat),
+            "audio" => Ok(RoomType::Audio),
+            _ => Err(()),
+        }
+    }
 }
 
 impl TryFrom<String> for DispatcherType {
@@ -39,66 +76,223 @@ impl TryFrom<String> for DispatcherType {
             "random" => Ok(DispatcherType::Random),
             "nextfreealpha" => Ok(DispatcherType::NextFreeAlphabetical),
             "nextfreerandom" => Ok(DispatcherType::NextFreeRandom),
+            "leastlisteners" => Ok(DispatcherType::LeastListeners),
+            "roundrobin" => Ok(DispatcherType::RoundRobin),
+            "maxlisteners" => Ok(DispatcherType::MaxListeners),
             _ => Err(()),
         }
     }
 }
 
 impl DispatcherType {
-    pub async fn choose_room(&self, rooms: Vec<Arc<RwLock<BroadcastRoom>>>) -> Option<Room> {
-        let mut empty_rooms: Vec<(String, Arc<RwLock<BroadcastRoom>>)> =
-            stream::iter(rooms.clone())
-                .then(|room| async move {
-                    let (listeners, name) = {
-                        let guard = room.read().await;
-                        let listeners = *guard.meta().num_listeners.borrow();
-                        let name = guard.meta().name.clone();
-                        (listeners, name)
-                    };
-                    (listeners <= 0, name, room)
-                })
-                .filter(|(ok, _name, _room)| futures::future::ready(*ok))
-                .map(|(_ok, name, room)| (name, room))
-                .collect()
-                .await;
+    pub async fn choose_room(
+        &self,
+        rooms: Vec<Arc<RwLock<BroadcastRoom>>>,
+        max_listeners: i32,
+        round_robin_counter: &AtomicUsize,
+    ) -> Option<Room> {
+        let mut candidates: Vec<(String, i32, Arc<RwLock<BroadcastRoom>>)> = stream::iter(rooms)
+            .then(|room| async move {
+                let (listeners, name) = {
+                    let guard = room.read().await;
+                    let listeners = *guard.meta().num_listeners.borrow();
+                    let name = guard.meta().name.clone();
+                    (listeners, name)
+                };
+                (name, listeners, room)
+            })
+            .collect()
+            .await;
+        // sort by name so the stateful and alphabetical strategies are deterministic
+        candidates.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let listeners: Vec<i32> = candidates
+            .iter()
+            .map(|(_name, listeners, _room)| *listeners)
+            .collect();
+        let index = self.choose_index(&listeners, max_listeners, round_robin_counter)?;
+        let (_, _, room) = &candidates[index];
+        Some((&*room.read().await).into())
+    }
+
+    /// index of the chosen room, given the number of listeners of each room
+    fn choose_index(
+        &self,
+        listeners: &[i32],
+        max_listeners: i32,
+        round_robin_counter: &AtomicUsize,
+    ) -> Option<usize> {
+        let indices: Vec<usize> = (0..listeners.len()).collect();
+        let empty_rooms: Vec<usize> = indices
+            .iter()
+            .copied()
+            .filter(|index| listeners[*index] <= 0)
+            .collect();
 
         match self {
-            DispatcherType::Random => {
-                if let Some(room) = rooms.choose(&mut StdRng::from_entropy()) {
-                    let room_lock = room.read().await;
-                    return Some((&*room_lock).into());
-                } else {
-                    None
-                }
+            DispatcherType::Random => indices.choose(&mut StdRng::from_entropy()).copied(),
+            DispatcherType::NextFreeAlphabetical => empty_rooms.first().copied(),
+            DispatcherType::NextFreeRandom => {
+                empty_rooms.choose(&mut StdRng::from_entropy()).copied()
             }
-            DispatcherType::NextFreeAlphabetical => {
-                empty_rooms.sort_by(|a, b| a.0.cmp(&b.0));
-                match empty_rooms.first() {
-                    Some((_, room)) => Some((&*room.read().await).into()),
-                    None => None,
-                }
+            DispatcherType::LeastListeners => {
+                indices.into_iter().min_by_key(|index| listeners[*index])
             }
-            DispatcherType::NextFreeRandom => {
-                match empty_rooms.choose(&mut StdRng::from_entropy()) {
-                    Some((_, room)) => Some((&*room.read().await).into()),
-                    None => None,
+            DispatcherType::RoundRobin => {
+                if listeners.is_empty() {
+                    None
+                } else {
+                    let index = round_robin_counter.fetch_add(1, Ordering::Relaxed);
+                    Some(index % listeners.len())
                 }
             }
+            DispatcherType::MaxListeners => indices
+                .into_iter()
+                .find(|index| listeners[*index] < max_listeners),
         }
     }
 }
 
+/// upper bound for the number of visitors waiting for a room of a dispatcher
+const MAX_QUEUE_LENGTH: usize = 1000;
+
+/// hex encoded HMAC-SHA256 of a message
+pub fn hmac_sha256_hex(key: &str, message: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(message);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// salted HMAC-SHA256 of a dispatcher password as `<salt>$<hex digest>`,
+/// so the password itself does not need to be stored
+fn hash_password(password: &str, salt: &str) -> String {
+    format!("{salt}${}", hmac_sha256_hex(salt, password.as_bytes()))
+}
+
 #[derive(Clone)]
 pub struct RoomDispatcher {
     pub name: String,
-    pub admin_password: String,
+    /// see [`hash_password`]
+    pub admin_password_hash: String,
     pub rule: Regex,
     pub room_type: RoomType,
     pub dispatcher_type: DispatcherType,
-    pub timeout_sender: tokio::sync::watch::Sender<Duration>,
-    pub timeout_receiver: tokio::sync::watch::Receiver<Duration>,
+    /// lifetime of the dispatcher after its last usage
+    pub timeout: Duration,
+    /// deadline after which the dispatcher gets deleted
+    pub timeout_sender: tokio::sync::watch::Sender<Instant>,
+    pub timeout_receiver: tokio::sync::watch::Receiver<Instant>,
     pub return_room_prefix: Option<String>,
     pub add_random_postfix: bool,
+    /// capacity of a room, used by [`DispatcherType::MaxListeners`]
+    pub max_listeners: i32,
+    /// position of [`DispatcherType::RoundRobin`], shared among all clones of the dispatcher
+    pub round_robin_counter: Arc<AtomicUsize>,
+    /// how long a visitor is sent to the same room, `None` disables sticky assignments
+    pub sticky_duration: Option<Duration>,
+    /// visitor token -> (room name, expiry of the assignment)
+    pub sticky_assignments: Arc<RwLock<HashMap<String, (String, Instant)>>>,
+    /// visitors waiting for a free room, first come first served
+    pub queue: Arc<RwLock<VecDeque<String>>>,
+}
+
+impl RoomDispatcher {
+    pub fn password_matches(&self, password: &str) -> bool {
+        match self.admin_password_hash.split_once('$') {
+            Some((salt, _)) => hash_password(password, salt) == self.admin_password_hash,
+            None => false,
+        }
+    }
+
+    /// postpones the deletion of the dispatcher by its timeout
+    pub fn renew(&self) {
+        let _ = self.timeout_sender.send(Instant::now() + self.timeout);
+    }
+
+    pub fn remaining_lifetime(&self) -> Duration {
+        self.timeout_receiver
+            .borrow()
+            .saturating_duration_since(Instant::now())
+    }
+
+    pub async fn choose_room(&self, rooms: Vec<Arc<RwLock<BroadcastRoom>>>) -> Option<Room> {
+        self.dispatcher_type
+            .choose_room(rooms, self.max_listeners, &self.round_robin_counter)
+            .await
+    }
+
+    /// returns the name of the room the visitor has been assigned to
+    /// if the assignment has not expired yet
+    pub async fn sticky_room(&self, visitor: &str) -> Option<String> {
+        self.sticky_duration?;
+        match self.sticky_assignments.read().await.get(visitor) {
+            Some((room_name, expires_at)) if *expires_at > Instant::now() => {
+                Some(room_name.clone())
+            }
+            _ => None,
+        }
+    }
+
+    pub async fn remember_room(&self, visitor: &str, room_name: &str) {
+        if let Some(sticky_duration) = self.sticky_duration {
+            let now = Instant::now();
+            let mut assignments = self.sticky_assignments.write().await;
+            assignments.retain(|_, (_, expires_at)| *expires_at > now);
+            assignments.insert(
+                visitor.to_string(),
+                (room_name.to_string(), now + sticky_duration),
+            );
+        }
+    }
+
+    /// puts the visitor at the end of the waiting queue unless it is already waiting
+    /// and returns its (0-based) position - `None` if the queue is full
+    pub async fn enqueue(&self, visitor: &str, room_events: &Sender<RoomEvent>) -> Option<usize> {
+        let mut queue = self.queue.write().await;
+        if let Some(position) = queue.iter().position(|v| v == visitor) {
+            return Some(position);
+        }
+        if queue.len() >= MAX_QUEUE_LENGTH {
+            return None;
+        }
+        queue.push_back(visitor.to_string());
+        let _ = room_events.send(RoomEvent::RoomDispatcherQueue(
+            self.name.clone(),
+            queue.len().try_into().unwrap_or(i32::MAX),
+        ));
+        Some(queue.len() - 1)
+    }
+
+    pub async fn dequeue(&self, visitor: &str, room_events: &Sender<RoomEvent>) {
+        let mut queue = self.queue.write().await;
+        if let Some(position) = queue.iter().position(|v| v == visitor) {
+            queue.remove(position);
+            let _ = room_events.send(RoomEvent::RoomDispatcherQueue(
+                self.name.clone(),
+                queue.len().try_into().unwrap_or(i32::MAX),
+            ));
+        }
+    }
+
+    pub async fn queue_position(&self, visitor: &str) -> Option<usize> {
+        self.queue.read().await.iter().position(|v| v == visitor)
+    }
+
+    pub async fn num_waiting(&self) -> usize {
+        self.queue.read().await.len()
+    }
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct StickyAssignment {
+    pub visitor: String,
+    pub room_name: String,
+    pub remaining_seconds: i32,
 }
 
 // graphql conversion
@@ -127,43 +321,165 @@ impl RoomDispatcher {
     async fn append_random_postfix(&self) -> bool {
         self.add_random_postfix
     }
+
+    async fn max_listeners(&self) -> i32 {
+        self.max_listeners
+    }
+
+    async fn sticky_timeout(&self) -> Option<i32> {
+        self.sticky_duration
+            .map(|duration| duration.as_secs().try_into().unwrap_or(i32::MAX))
+    }
+
+    /// requires the admin password of the dispatcher, as the visitor tokens
+    /// identify the visitors
+    async fn sticky_assignments(
+        &self,
+        admin_password: String,
+    ) -> anyhow::Result<Vec<StickyAssignment>> {
+        if !self.password_matches(&admin_password) {
+            return Err(anyhow::anyhow!("Password of dispatcher does not match"));
+        }
+        let now = Instant::now();
+        Ok(self
+            .sticky_assignments
+            .read()
+            .await
+            .iter()
+            .filter(|(_, (_, expires_at))| *expires_at > now)
+            .map(|(visitor, (room_name, expires_at))| StickyAssignment {
+                visitor: visitor.clone(),
+                room_name: room_name.clone(),
+                remaining_seconds: (*expires_at - now).as_secs().try_into().unwrap_or(i32::MAX),
+            })
+            .collect())
+    }
+
+    async fn queue_length(&self) -> i32 {
+        self.num_waiting().await.try_into().unwrap_or(i32::MAX)
+    }
+
+    async fn timeout(&self) -> i32 {
+        self.timeout.as_secs().try_into().unwrap_or(i32::MAX)
+    }
+
+    /// seconds until the dispatcher gets deleted unless it gets used
+    async fn remaining_lifetime(&self) -> i32 {
+        self.remaining_lifetime()
+            .as_secs()
+            .try_into()
+            .unwrap_or(i32::MAX)
+    }
+
+    /// rooms which are currently matched by the rule of the dispatcher
+    async fn matching_rooms<'a>(&self, ctx: &Context<'a>) -> Vec<Room> {
+        ctx.data_unchecked::<Arc<AppState>>()
+            .get_rooms(&self.room_type)
+            .await
+            .into_iter()
+            .filter(|room| self.rule.is_match(&room.name))
+            .collect()
+    }
 }
 
-#[derive(InputObject, Clone)]
+#[derive(InputObject, Clone, Serialize, Deserialize)]
 pub struct RoomDispatcherInput {
     pub name: String,
     pub admin_password: Option<String>,
+    /// stored instead of the admin password once the dispatcher gets persisted
+    #[graphql(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin_password_hash: Option<String>,
     pub rule: String,
     pub room_type: RoomType,
     pub dispatcher_type: DispatcherType,
     pub timeout: i32,
     pub return_room_prefix: Option<String>,
     pub add_random_postfix: bool,
+    /// only used by the `MAX_LISTENERS` dispatcher type, defaults to 1
+    pub max_listeners: Option<i32>,
+    /// seconds a visitor gets sent to the same room again, disabled if not set or 0
+    pub sticky_timeout: Option<i32>,
+}
+
+impl From<&RoomDispatcher> for RoomDispatcherInput {
+    fn from(value: &RoomDispatcher) -> Self {
+        RoomDispatcherInput {
+            name: value.name.clone(),
+            admin_password: None,
+            admin_password_hash: Some(value.admin_password_hash.clone()),
+            rule: value.rule.as_str().to_string(),
+            room_type: value.room_type,
+            dispatcher_type: value.dispatcher_type,
+            timeout: value.timeout.as_secs().try_into().unwrap_or(i32::MAX),
+            return_room_prefix: value.return_room_prefix.clone(),
+            add_random_postfix: value.add_random_postfix,
+            max_listeners: Some(value.max_listeners),
+            sticky_timeout: value
+                .sticky_duration
+                .map(|duration| duration.as_secs().try_into().unwrap_or(i32::MAX)),
+        }
+    }
+}
+
+/// fields of a dispatcher which can be changed after its creation,
+/// fields which are not set stay untouched
+#[derive(InputObject, Clone, Deserialize)]
+pub struct RoomDispatcherUpdate {
+    pub rule: Option<String>,
+    pub dispatcher_type: Option<DispatcherType>,
+    pub timeout: Option<i32>,
+    /// `null` removes the prefix
+    #[serde(default)]
+    pub return_room_prefix: MaybeUndefined<String>,
+    pub add_random_postfix: Option<bool>,
+    pub max_listeners: Option<i32>,
+    /// 0 disables sticky assignments
+    pub sticky_timeout: Option<i32>,
 }
 
-impl From<RoomDispatcherInput> for RoomDispatcher {
-    fn from(value: RoomDispatcherInput) -> Self {
+impl TryFrom<RoomDispatcherInput> for RoomDispatcher {
+    type Error = anyhow::Error;
+
+    fn try_from(value: RoomDispatcherInput) -> Result<Self, Self::Error> {
+        let timeout = Duration::from_secs(value.timeout.try_into()?);
+        let rule = Regex::new(&value.rule)?;
+        let sticky_duration = match value.sticky_timeout {
+            Some(timeout) if timeout > 0 => Some(Duration::from_secs(timeout.try_into()?)),
+            _ => None,
+        };
         let (timeout_sender, timeout_receiver) =
-            tokio::sync::watch::channel(Duration::from_secs(value.timeout.try_into().unwrap()));
-        RoomDispatcher {
+            tokio::sync::watch::channel(Instant::now() + timeout);
+        let salt = || Alphanumeric.sample_string(&mut StdRng::from_entropy(), 16);
+        let admin_password_hash = match (value.admin_password, value.admin_password_hash) {
+            (Some(pw), _) => hash_password(&pw, &salt()),
+            (None, Some(hash)) => hash,
+            (None, None) => hash_password(
+                &Alphanumeric.sample_string(&mut StdRng::from_entropy(), 8),
+                &salt(),
+            ),
+        };
+        Ok(RoomDispatcher {
             name: value.name,
-            admin_password: if let Some(pw) = value.admin_password {
-                pw
-            } else {
-                Alphanumeric.sample_string(&mut StdRng::from_entropy(), 8)
-            },
-            rule: Regex::new(&value.rule).unwrap(),
+            admin_password_hash,
+            rule,
             room_type: value.room_type,
             dispatcher_type: value.dispatcher_type,
+            timeout,
             timeout_sender,
             timeout_receiver,
             return_room_prefix: value.return_room_prefix,
             add_random_postfix: value.add_random_postfix,
-        }
+            max_listeners: value.max_listeners.unwrap_or(1),
+            round_robin_counter: Arc::new(AtomicUsize::new(0)),
+            sticky_duration,
+            sticky_assignments: Arc::new(RwLock::new(HashMap::new())),
+            queue: Arc::new(RwLock::new(VecDeque::new())),
+        })
     }
 }
 
-#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 // #[graphql(remote = "shared::models::RoomType")]
 pub enum RoomType {
     Float,
@@ -195,9 +511,16 @@ impl BroadcastRoom {
         }
     }
 
-    pub async fn join_room(&self, offer: &str) -> anyhow::Result<ResponseOffer> {
+    pub async fn join_room(
+        &self,
+        offer: &str,
+        participant: Option<JamParticipantInput>,
+    ) -> anyhow::Result<ResponseOffer> {
         match self {
-            BroadcastRoom::Data(data_room) => data_room.join_room(offer).await,
+            BroadcastRoom::Data(data_room) => data_room.join_room(offer, participant).await,
+            BroadcastRoom::Audio(_) if participant.is_some() => {
+                Err(anyhow::anyhow!("Audio rooms can not be jam rooms"))
+            }
             BroadcastRoom::Audio(audio_room) => audio_room.join_room(offer).await,
         }
     }
@@ -210,7 +533,10 @@ impl BroadcastRoom {
     ) -> anyhow::Result<ResponseOffer> {
         match self {
             BroadcastRoom::Audio(audio_room) => audio_room.replace_sender(offer.to_string()).await,
-            BroadcastRoom::Data(data_broadcast_room) => todo!(),
+            BroadcastRoom::Data(_) => Err(StateError::NotSupported(
+                "The sender of a data room can not be replaced".to_string(),
+            )
+            .into()),
         }
     }
 }
@@ -231,6 +557,88 @@ pub struct BroadcastRoomMeta {
     // this receiver does not need to be public accessible
     _num_listeners_receiver: tokio::sync::watch::Receiver<i32>,
     pub room_events: Sender<RoomEvent>,
+    pub lifetime: RoomLifetime,
+    pub shutdown: Shutdown,
+}
+
+/// upper bound for the characters of the feedback a listener can send to the sender of a room
+const MAX_FEEDBACK_LENGTH: usize = 280;
+/// listeners can send feedback at most once within this duration
+const MIN_FEEDBACK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// handles the meta messages of a listener - pings get answered and feedback gets
+/// forwarded to the sender of the room and as room event, rate limited by
+/// [`MIN_FEEDBACK_INTERVAL`]
+struct ListenerMeta {
+    room_type: RoomType,
+    room_name: String,
+    meta_reply: Sender<SteckerData>,
+    room_events: Sender<RoomEvent>,
+    /// meta channel of the listener
+    outbound: Sender<SteckerData>,
+    last_feedback: Option<Instant>,
+}
+
+impl ListenerMeta {
+    fn new(room_type: RoomType, meta: &BroadcastRoomMeta, outbound: Sender<SteckerData>) -> Self {
+        Self {
+            room_type,
+            room_name: meta.name.clone(),
+            meta_reply: meta.meta_reply.clone(),
+            room_events: meta.room_events.clone(),
+            outbound,
+            last_feedback: None,
+        }
+    }
+
+    fn handle(&mut self, msg: SteckerData) {
+        match MetaMessage::decode(&msg) {
+            Ok(MetaMessage::Feedback { message }) => self.forward_feedback(message),
+            Ok(MetaMessage::Ping { timestamp }) => {
+                let _ = self.outbound.send(MetaMessage::pong(timestamp).encode());
+            }
+            Ok(meta_msg) => warn!(?meta_msg, "Meta message from listener will be ignored"),
+            Err(err) => warn!(?err, "Ignore invalid meta message from listener"),
+        }
+    }
+
+    fn forward_feedback(&mut self, message: String) {
+        let message = message.trim();
+        let length = message.chars().count();
+        if length == 0 || length > MAX_FEEDBACK_LENGTH {
+            warn!(length, "Drop feedback with invalid length");
+            return;
+        }
+        if self
+            .last_feedback
+            .is_some_and(|last_feedback| last_feedback.elapsed() < MIN_FEEDBACK_INTERVAL)
+        {
+            debug!("Drop feedback because of rate limit");
+            return;
+        }
+        self.last_feedback = Some(Instant::now());
+        trace!(message, "Forward feedback of listener");
+        let _ = self.meta_reply.send(
+            MetaMessage::Feedback {
+                message: message.to_string(),
+            }
+            .encode(),
+        );
+        let _ = self.room_events.send(RoomEvent::BroadcastRoomFeedback(
+            self.room_type,
+            self.room_name.clone(),
+            message.to_string(),
+        ));
+    }
+}
+
+/// how long a room is kept alive
+#[derive(Debug, Clone, Copy)]
+pub struct RoomLifetime {
+    /// the room gets deleted if its sender does not send anything for this duration
+    pub inactivity_timeout: Duration,
+    /// the room gets deleted at this point in time regardless of its activity
+    pub expires_at: Option<Instant>,
 }
 
 impl From<RoomType> for DataRoomInternalType {
@@ -244,12 +652,310 @@ impl From<RoomType> for DataRoomInternalType {
     }
 }
 
-#[derive(SimpleObject, Clone)]
+#[derive(SimpleObject, Clone, Serialize)]
+#[graphql(complex)]
 pub struct Room {
     pub uuid: String,
     pub name: String,
     pub num_listeners: i32,
     pub room_type: RoomType,
+    /// seconds without any signal from the sender after which the room gets deleted
+    pub inactivity_timeout: i32,
+    /// seconds until the room expires, not set if the room does not expire
+    pub remaining_lifetime: Option<i32>,
+    /// set for jam rooms, in which listeners may send as well
+    pub merge_policy: Option<MergePolicy>,
+    /// set for crowd rooms, in which every listener contributes a value
+    pub crowd: bool,
+}
+
+#[ComplexObject]
+impl Room {
+    /// room whose listener reserved this room as its return room
+    async fn source_room<'a>(&self, ctx: &Context<'a>) -> Option<String> {
+        ctx.data_unchecked::<Arc<AppState>>()
+            .return_rooms
+            .read()
+            .await
+            .get(&self.name)
+            .map(|return_room| return_room.source_room.clone())
+    }
+
+    /// return rooms which have been reserved for the listeners of this room
+    async fn return_rooms<'a>(&self, ctx: &Context<'a>) -> Vec<String> {
+        ctx.data_unchecked::<Arc<AppState>>()
+            .return_rooms
+            .read()
+            .await
+            .iter()
+            .filter(|(_, return_room)| return_room.source_room == self.name)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+/// A room name reserved by the server for a dispatched listener,
+/// so the listener can send its signal back to the sender of the source room.
+#[derive(Clone, Debug)]
+pub struct ReturnRoom {
+    pub source_room: String,
+    /// token of the visitor the room has been reserved for
+    pub visitor: String,
+    pub password: String,
+    pub reserved_at: Instant,
+}
+
+/// how the messages of concurrent senders of a jam room get merged
+/// before they are broadcasted
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum MergePolicy {
+    /// the most recent message gets broadcasted as is
+    LastWriterWins,
+    /// sum of the most recent values of all senders
+    Sum,
+    /// mean of the most recent values of all senders
+    Mean,
+    /// smallest of the most recent values of all senders
+    Min,
+    /// largest of the most recent values of all senders
+    Max,
+}
+
+/// turns a data room into a jam room, in which listeners may send as well
+#[derive(InputObject, Clone, Debug, Deserialize)]
+pub struct JamInput {
+    pub merge_policy: MergePolicy,
+    /// password listeners need in order to send, any listener may send if not set
+    pub password: Option<String>,
+}
+
+/// a listener which wants to send into a jam room
+#[derive(InputObject, Clone, Debug, Default, Deserialize)]
+pub struct JamParticipantInput {
+    /// identity which gets attached to the messages of the listener,
+    /// a random one is used if not set
+    pub sender_name: Option<String>,
+    pub password: Option<String>,
+}
+
+/// identity of the creator of a jam room
+const JAM_CREATOR: &str = "creator";
+/// listeners of a jam room can send at most once within this duration,
+/// further messages get dropped
+const MIN_JAM_MESSAGE_INTERVAL: Duration = Duration::from_millis(20);
+
+/// merges the messages of the senders of a jam room
+#[derive(Debug, Clone)]
+pub struct JamMixer {
+    pub policy: MergePolicy,
+    password: Option<String>,
+    /// names of the listeners which currently send into the room
+    senders: Arc<std::sync::Mutex<HashSet<String>>>,
+    /// most recent value of each sender
+    values: Arc<std::sync::Mutex<HashMap<String, f32>>>,
+}
+
+impl JamMixer {
+    pub fn new(settings: JamInput, room_type: &RoomType) -> anyhow::Result<Self> {
+        match room_type {
+            RoomType::Audio => return Err(anyhow::anyhow!("Audio rooms can not be jam rooms")),
+            RoomType::Chat if settings.merge_policy != MergePolicy::LastWriterWins => {
+                return Err(anyhow::anyhow!(
+                    "Chat rooms can only use the LastWriterWins merge policy"
+                ))
+            }
+            _ => {}
+        }
+        Ok(Self {
+            policy: settings.merge_policy,
+            password: settings.password,
+            senders: Arc::new(std::sync::Mutex::new(HashSet::new())),
+            values: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// identity of a listener which is allowed to send into the room, which stays
+    /// taken until the listener [leaves](Self::leave) the room
+    fn sender_name(&self, participant: JamParticipantInput) -> anyhow::Result<Option<String>> {
+        if self.password.is_some() && participant.password != self.password {
+            return match participant.password {
+                Some(_) => Err(anyhow::anyhow!("Password does not match")),
+                None => Ok(None),
+            };
+        }
+        let sender_name = match participant.sender_name {
+            Some(sender_name) if sender_name == JAM_CREATOR => {
+                return Err(anyhow::anyhow!("The sender name {JAM_CREATOR} is reserved"))
+            }
+            Some(sender_name) => sender_name,
+            None => format!("listener-{}", &Uuid::new_v4().simple().to_string()[..8]),
+        };
+        if !self.senders.lock().unwrap().insert(sender_name.clone()) {
+            return Err(anyhow::anyhow!(
+                "The sender name {sender_name} is already taken"
+            ));
+        }
+        Ok(Some(sender_name))
+    }
+
+    fn merged(&self, values: &HashMap<String, f32>, value: f32) -> f32 {
+        match self.policy {
+            MergePolicy::LastWriterWins => value,
+            MergePolicy::Sum => values.values().sum(),
+            MergePolicy::Mean => values.values().sum::<f32>() / values.len().max(1) as f32,
+            MergePolicy::Min => values.values().copied().fold(f32::INFINITY, f32::min),
+            MergePolicy::Max => values.values().copied().fold(f32::NEG_INFINITY, f32::max),
+        }
+    }
+
+    /// message which gets broadcasted after `sender` sent `data`
+    pub fn merge(&self, sender: &str, data: SteckerData) -> SteckerData {
+        let SteckerData::F32(value) = data else {
+            return data;
+        };
+        let mut values = self.values.lock().unwrap();
+        values.insert(sender.to_string(), value);
+        SteckerData::F32(self.merged(&values, value))
+    }
+
+    /// forgets the name and value of a sender which left the room and returns
+    /// the merged value of the remaining senders if it changed
+    pub fn leave(&self, sender: &str) -> Option<SteckerData> {
+        self.senders.lock().unwrap().remove(sender);
+        let mut values = self.values.lock().unwrap();
+        let value = values.remove(sender)?;
+        if self.policy == MergePolicy::LastWriterWins || values.is_empty() {
+            return None;
+        }
+        Some(SteckerData::F32(self.merged(&values, value)))
+    }
+}
+
+/// turns a float room into a crowd room, in which every listener contributes a value
+#[derive(InputObject, Clone, Debug, Default, Deserialize)]
+pub struct CrowdInput {
+    /// aggregates which get published per second, defaults to 10
+    pub rate: Option<f64>,
+    /// number of bins of the histogram, defaults to 8
+    pub histogram_bins: Option<i32>,
+    /// lower bound of the histogram, defaults to 0
+    pub min: Option<f32>,
+    /// upper bound of the histogram, defaults to 1
+    pub max: Option<f32>,
+}
+
+/// upper bound for [`CrowdInput::rate`]
+const MAX_CROWD_RATE: f64 = 50.0;
+/// upper bound for [`CrowdInput::histogram_bins`]
+const MAX_CROWD_HISTOGRAM_BINS: i32 = 64;
+
+/// collects the most recent value of each listener of a crowd room
+#[derive(Debug, Clone)]
+pub struct CrowdAggregator {
+    /// time between publishing the aggregates
+    pub interval: Duration,
+    histogram_bins: usize,
+    min: f32,
+    max: f32,
+    contributions: Arc<std::sync::Mutex<HashMap<Uuid, f32>>>,
+}
+
+/// aggregated contributions of the listeners of a crowd room
+#[derive(Debug, Clone)]
+pub struct CrowdAggregates {
+    /// not set if nobody contributed yet
+    pub mean: Option<f32>,
+    /// not set if nobody contributed yet
+    pub median: Option<f32>,
+    pub count: usize,
+    /// number of values within each bin between min and max
+    pub histogram: Vec<usize>,
+}
+
+impl CrowdAggregator {
+    pub fn new(settings: CrowdInput, room_type: &RoomType) -> anyhow::Result<Self> {
+        if *room_type != RoomType::Float {
+            return Err(anyhow::anyhow!("Only float rooms can be crowd rooms"));
+        }
+        let rate = settings.rate.unwrap_or(10.0);
+        if rate.is_nan() || rate <= 0.0 || rate > MAX_CROWD_RATE {
+            return Err(anyhow::anyhow!(
+                "The rate of a crowd room needs to be within (0, {MAX_CROWD_RATE}]"
+            ));
+        }
+        let histogram_bins = settings.histogram_bins.unwrap_or(8);
+        if !(1..=MAX_CROWD_HISTOGRAM_BINS).contains(&histogram_bins) {
+            return Err(anyhow::anyhow!(
+                "A crowd room can have between 1 and {MAX_CROWD_HISTOGRAM_BINS} histogram bins"
+            ));
+        }
+        let min = settings.min.unwrap_or(0.0);
+        let max = settings.max.unwrap_or(1.0);
+        if !(min.is_finite() && max.is_finite()) || min >= max {
+            return Err(anyhow::anyhow!(
+                "The minimum of a crowd room needs to be below its maximum"
+            ));
+        }
+        Ok(Self {
+            interval: Duration::from_secs_f64(1.0 / rate),
+            histogram_bins: histogram_bins as usize,
+            min,
+            max,
+            contributions: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        })
+    }
+
+    pub fn contribute(&self, listener: Uuid, value: f32) {
+        if value.is_finite() {
+            self.contributions.lock().unwrap().insert(listener, value);
+        }
+    }
+
+    pub fn leave(&self, listener: &Uuid) {
+        self.contributions.lock().unwrap().remove(listener);
+    }
+
+    pub fn aggregates(&self) -> CrowdAggregates {
+        let mut values: Vec<f32> = self
+            .contributions
+            .lock()
+            .unwrap()
+            .values()
+            .copied()
+            .collect();
+        values.sort_by(f32::total_cmp);
+
+        let count = values.len();
+        let mean = (count > 0).then(|| values.iter().sum::<f32>() / count as f32);
+        let median = (count > 0).then(|| match count % 2 {
+            0 => (values[count / 2 - 1] + values[count / 2]) / 2.0,
+            _ => values[count / 2],
+        });
+        let mut histogram = vec![0; self.histogram_bins];
+        let bin_width = (self.max - self.min) / self.histogram_bins as f32;
+        for value in values {
+            let bin = ((value.clamp(self.min, self.max) - self.min) / bin_width) as usize;
+            histogram[bin.min(self.histogram_bins - 1)] += 1;
+        }
+        CrowdAggregates {
+            mean,
+            median,
+            count,
+            histogram,
+        }
+    }
+}
+
+impl CrowdAggregates {
+    pub fn meta_message(self, room: String) -> MetaMessage {
+        MetaMessage::CrowdAggregates {
+            room,
+            mean: self.mean,
+            median: self.median,
+            count: self.count,
+            histogram: self.histogram,
+        }
+    }
 }
 
 // server state objects
@@ -260,13 +966,103 @@ pub struct DataBroadcastRoom {
     /// potentially not interesting to subscribe to this
     pub reply: Sender<SteckerData>,
     /// Subscribe to this to receive messages from room
-    /// potentially not useful to send to this (unless you also become a broadcaster)
+    /// messages get sent via [`RoomHistory::publish`], so late joiners do not miss them
     pub broadcast: Sender<SteckerData>,
+    /// triggers when the data channel of the creator was closed
+    pub close: Sender<()>,
     pub room_type: DataRoomInternalType,
+    /// recently broadcasted messages
+    pub history: RoomHistory,
+    /// number of recent messages which get replayed to new listeners
+    pub replay_length: usize,
+    /// set if listeners may send into the room as well
+    pub jam: Option<JamMixer>,
+    /// set if the values of the listeners get aggregated
+    pub crowd: Option<CrowdAggregator>,
+}
+
+/// a message which has been broadcasted within a data room
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub data: SteckerData,
+    /// unix timestamp in milliseconds
+    pub timestamp: i64,
+}
+
+/// the most recent messages of a data room, older messages get dropped
+/// once the capacity is reached.
+/// Messages get recorded and broadcasted under the same lock, so late joiners
+/// can take the history and subscribe without missing or repeating a message.
+#[derive(Debug, Clone)]
+pub struct RoomHistory {
+    capacity: usize,
+    broadcast: Sender<SteckerData>,
+    entries: Arc<std::sync::Mutex<VecDeque<HistoryEntry>>>,
+}
+
+impl RoomHistory {
+    pub fn new(capacity: usize, broadcast: Sender<SteckerData>) -> Self {
+        Self {
+            capacity,
+            broadcast,
+            entries: Arc::new(std::sync::Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    /// records a message and broadcasts it to the listeners of the room
+    pub fn publish(
+        &self,
+        data: SteckerData,
+    ) -> Result<usize, tokio::sync::broadcast::error::SendError<SteckerData>> {
+        let mut entries = self.entries.lock().unwrap();
+        if self.capacity > 0 {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_millis().try_into().unwrap_or(i64::MAX))
+                .unwrap_or(0);
+            if entries.len() >= self.capacity {
+                entries.pop_front();
+            }
+            entries.push_back(HistoryEntry {
+                data: data.clone(),
+                timestamp,
+            });
+        }
+        self.broadcast.send(data)
+    }
+
+    /// the last `n` entries, oldest first
+    pub fn last(&self, n: usize) -> Vec<HistoryEntry> {
+        Self::last_entries(&self.entries.lock().unwrap(), n)
+    }
+
+    /// the last `n` entries, oldest first, and a receiver for all messages
+    /// which get published afterwards
+    pub fn subscribe(
+        &self,
+        n: usize,
+    ) -> (
+        Vec<HistoryEntry>,
+        tokio::sync::broadcast::Receiver<SteckerData>,
+    ) {
+        let entries = self.entries.lock().unwrap();
+        (Self::last_entries(&entries, n), self.broadcast.subscribe())
+    }
+
+    fn last_entries(entries: &VecDeque<HistoryEntry>, n: usize) -> Vec<HistoryEntry> {
+        entries
+            .iter()
+            .skip(entries.len().saturating_sub(n))
+            .cloned()
+            .collect()
+    }
 }
 
 type ResponseOffer = String;
 
+/// capacity of the channels of rooms which are owned by the server
+const SERVER_ROOM_CAPACITY: usize = 1024;
+
 pub struct BroadcastRoomWithOffer {
     pub broadcast_room: DataBroadcastRoom,
     pub offer: ResponseOffer,
@@ -280,6 +1076,12 @@ impl DataBroadcastRoom {
         room_type: DataRoomInternalType,
         password: String,
         room_events: Sender<RoomEvent>,
+        lifetime: RoomLifetime,
+        shutdown: Shutdown,
+        history_length: usize,
+        replay_length: usize,
+        jam: Option<JamMixer>,
+        crowd: Option<CrowdAggregator>,
     ) -> anyhow::Result<BroadcastRoomWithOffer> {
         info!("Something else");
         let connection = SteckerWebRTCConnection::build_connection()
@@ -297,6 +1099,7 @@ impl DataBroadcastRoom {
             .start_listening_for_data_channel()
             .instrument(Span::current())
             .await;
+        shutdown.close_on_shutdown(connection);
 
         let (num_listeners_sender, num_listeners_receiver) = tokio::sync::watch::channel(0);
 
@@ -307,6 +1110,7 @@ impl DataBroadcastRoom {
         let meta_outbound = meta_channel.outbound.clone();
         let mut meta_inbound = meta_channel.inbound.subscribe();
         let name2 = name.clone();
+        let room_events2 = room_events.clone();
         tokio::spawn(
             async move {
                 loop {
@@ -314,20 +1118,28 @@ impl DataBroadcastRoom {
                         _ = num_listeners_receiver2.changed() => {
                             let cur_num_listeners = *num_listeners_receiver2.borrow();
                             info!(cur_num_listeners, "Changed number of listeners");
-                            let _ = meta_outbound.send(SteckerData::String(
-                                format!("Number of listeners @ {name2}: {cur_num_listeners}")
+                            let _ = room_events2.send(RoomEvent::BroadcastRoomUserCount(
+                                room_type.into(),
+                                name2.clone(),
+                                cur_num_listeners,
                             ));
+                            let _ = meta_outbound.send(MetaMessage::ListenerCount {
+                                room: name2.clone(),
+                                num_listeners: cur_num_listeners,
+                            }.encode());
                         },
                         raw_meta_msg = meta_inbound.recv() => {
                             match raw_meta_msg {
                                 Ok(meta_msg) => {
-                                    match meta_msg {
-                                        SteckerData::String(msg) => {
-                                            trace!(msg, "Received meta_message form creator");
+                                    // pings of the creator get answered, all of its meta messages
+                                    // reach the listeners via `meta_broadcast`
+                                    match MetaMessage::decode(&meta_msg) {
+                                        Ok(MetaMessage::Ping { timestamp }) => {
+                                            let _ = meta_outbound.send(MetaMessage::pong(timestamp).encode());
                                         },
-                                        _ => {error!("Received f32 from meta message?!");}
+                                        Ok(msg) => trace!(?msg, "Received meta message from creator"),
+                                        Err(err) => warn!(?err, "Received invalid meta message from creator"),
                                     }
-
                                 },
                                 Err(_) => {
                                     error!("Could not receive meta message from creator");
@@ -342,6 +1154,38 @@ impl DataBroadcastRoom {
             .instrument(Span::current()),
         );
 
+        // messages of the creator get recorded in the history before they are broadcasted,
+        // in jam rooms they get merged with the ones of the listeners
+        let (broadcast, _) = tokio::sync::broadcast::channel(SERVER_ROOM_CAPACITY);
+        let history = RoomHistory::new(history_length.max(replay_length), broadcast.clone());
+        let history2 = history.clone();
+        let jam2 = jam.clone();
+        let mut creator_messages = stecker_data_channel.inbound.subscribe();
+        let mut creator_close = stecker_data_channel.close.subscribe();
+        tokio::spawn(
+            async move {
+                loop {
+                    tokio::select! {
+                        msg = creator_messages.recv() => {
+                            match msg {
+                                Ok(msg) => {
+                                    let msg = match &jam2 {
+                                        Some(jam) => jam.merge(JAM_CREATOR, msg),
+                                        None => msg,
+                                    };
+                                    let _ = history2.publish(msg);
+                                },
+                                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {},
+                            }
+                        },
+                        _ = creator_close.recv() => break,
+                    }
+                }
+            }
+            .instrument(Span::current()),
+        );
+
         let broadcast_room = DataBroadcastRoom {
             meta: BroadcastRoomMeta {
                 name: name,
@@ -352,10 +1196,17 @@ impl DataBroadcastRoom {
                 _num_listeners_receiver: num_listeners_receiver,
                 admin_password: password,
                 room_events,
+                lifetime,
+                shutdown,
             },
             room_type: room_type,
             reply: stecker_data_channel.outbound.clone(),
-            broadcast: stecker_data_channel.inbound.clone(),
+            broadcast,
+            close: stecker_data_channel.close.clone(),
+            history,
+            replay_length,
+            jam,
+            crowd,
         };
 
         Ok(BroadcastRoomWithOffer {
@@ -364,20 +1215,127 @@ impl DataBroadcastRoom {
         })
     }
 
+    /// creates a room without a WebRTC creator - the server itself publishes
+    /// into [`DataBroadcastRoom::history`], e.g. values pushed via HTTP
+    #[instrument(skip_all)]
+    pub fn create_server_room(
+        name: String,
+        room_type: DataRoomInternalType,
+        password: String,
+        room_events: Sender<RoomEvent>,
+        lifetime: RoomLifetime,
+        shutdown: Shutdown,
+        history_length: usize,
+        replay_length: usize,
+    ) -> DataBroadcastRoom {
+        let (broadcast, _) = tokio::sync::broadcast::channel(SERVER_ROOM_CAPACITY);
+        let (reply, _) = tokio::sync::broadcast::channel(SERVER_ROOM_CAPACITY);
+        let (meta_broadcast, _) = tokio::sync::broadcast::channel(SERVER_ROOM_CAPACITY);
+        let (meta_reply, _) = tokio::sync::broadcast::channel(SERVER_ROOM_CAPACITY);
+        let (close, _) = tokio::sync::broadcast::channel(1);
+        let (num_listeners_sender, num_listeners_receiver) = tokio::sync::watch::channel(0);
+
+        let mut num_listeners_receiver2 = num_listeners_receiver.clone();
+        let room_events2 = room_events.clone();
+        let name2 = name.clone();
+        tokio::spawn(
+            async move {
+                while num_listeners_receiver2.changed().await.is_ok() {
+                    let cur_num_listeners = *num_listeners_receiver2.borrow();
+                    info!(cur_num_listeners, "Changed number of listeners");
+                    let _ = room_events2.send(RoomEvent::BroadcastRoomUserCount(
+                        room_type.into(),
+                        name2.clone(),
+                        cur_num_listeners,
+                    ));
+                }
+            }
+            .instrument(Span::current()),
+        );
+
+        DataBroadcastRoom {
+            meta: BroadcastRoomMeta {
+                name,
+                uuid: Uuid::new_v4(),
+                meta_broadcast,
+                meta_reply,
+                num_listeners: num_listeners_sender,
+                _num_listeners_receiver: num_listeners_receiver,
+                admin_password: password,
+                room_events,
+                lifetime,
+                shutdown,
+            },
+            room_type,
+            reply,
+            broadcast: broadcast.clone(),
+            close,
+            history: RoomHistory::new(history_length.max(replay_length), broadcast),
+            replay_length,
+            jam: None,
+            crowd: None,
+        }
+    }
+
     #[instrument(skip_all, err)]
-    pub async fn join_room(&self, offer: &str) -> anyhow::Result<ResponseOffer> {
-        let connection = SteckerWebRTCConnection::build_connection().await?;
-        let response_offer = connection.respond_to_offer(offer.to_string()).await?;
+    pub async fn join_room(
+        &self,
+        offer: &str,
+        participant: Option<JamParticipantInput>,
+    ) -> anyhow::Result<ResponseOffer> {
+        // listeners of a jam room may send as well
+        let jam_sender = match (&self.jam, participant) {
+            (Some(jam), participant) => jam
+                .sender_name(participant.unwrap_or_default())?
+                .map(|sender_name| (jam.clone(), sender_name)),
+            (None, Some(_)) => {
+                return Err(anyhow::anyhow!(
+                    "{} does not accept messages from listeners",
+                    self.meta.name
+                ))
+            }
+            (None, None) => None,
+        };
+
+        let connection_result = async {
+            let connection = SteckerWebRTCConnection::build_connection().await?;
+            let response_offer = connection.respond_to_offer(offer.to_string()).await?;
+            anyhow::Ok((connection, response_offer))
+        }
+        .await;
+        let (connection, response_offer) = match connection_result {
+            Ok(result) => result,
+            Err(err) => {
+                // the name of the sender can be used again
+                if let Some((jam, sender_name)) = &jam_sender {
+                    jam.leave(sender_name);
+                }
+                return Err(err);
+            }
+        };
 
         let meta_channel = connection.register_channel(&DataRoomInternalType::Meta);
         let stecker_data_channel = connection.register_channel(&self.room_type.into());
+        // subscribe before the channel can be opened by the listener
+        let mut open_receiver = stecker_data_channel.open.subscribe();
         connection
             .start_listening_for_data_channel()
             .instrument(Span::current())
             .await;
-
-        let room_rx = self.broadcast.clone();
+        self.meta.shutdown.close_on_shutdown(connection);
+
+        let history = self.history.clone();
+        let replay_length = self.replay_length;
+        let crowd = self.crowd.clone();
+        let listener_uuid = Uuid::new_v4();
+        let mut listener_meta = ListenerMeta::new(
+            self.room_type.into(),
+            &self.meta,
+            meta_channel.outbound.clone(),
+        );
+        let room_name = self.meta.name.clone();
         let meta_rx = self.meta.meta_broadcast.clone();
+        let meta_reply = self.meta.meta_reply.clone();
         let close_trigger2 = stecker_data_channel.close.clone();
 
         let num_listeners2 = self.meta.num_listeners.clone();
@@ -388,7 +1346,9 @@ impl DataBroadcastRoom {
         let mut num_listeners_receiver = self.meta.num_listeners.subscribe();
 
         tokio::spawn(async move {
-            let mut room_receiver = room_rx.subscribe();
+            // subscribed once the replay has been sent, so the listener receives
+            // neither duplicates nor replayed messages after newer ones
+            let mut room_receiver: Option<tokio::sync::broadcast::Receiver<SteckerData>> = None;
             let mut meta_receiver = meta_rx.subscribe();
             let mut stop_receiver = stecker_data_channel.close.subscribe();
 
@@ -396,29 +1356,58 @@ impl DataBroadcastRoom {
             let mut inbound_receiver = stecker_data_channel.inbound.subscribe();
             let mut meta_inbound_receiver = meta_channel.inbound.subscribe();
             let mut stop_receiver2 = close_trigger2.subscribe();
+            let mut last_jam_message: Option<Instant> = None;
 
             loop {
                 tokio::select! {
-                    raw_msg = room_receiver.recv() => {
+                    Ok(_) = open_receiver.recv(), if room_receiver.is_none() => {
+                        // late joiners receive the most recent messages of the room
+                        let (replay, receiver) = history.subscribe(replay_length);
+                        for entry in replay {
+                            let _ = stecker_data_channel.outbound.send(entry.data);
+                        }
+                        room_receiver = Some(receiver);
+                    },
+                    raw_msg = async { room_receiver.as_mut().unwrap().recv().await }, if room_receiver.is_some() => {
                         match raw_msg {
                             Ok(msg) => {
                                 let _ = stecker_data_channel.outbound.send(msg);
                             },
                             Err(err) => {
-                                while room_receiver.len() > 0 {
-                                    let _ = room_receiver.recv().await;
+                                if let Some(room_receiver) = room_receiver.as_mut() {
+                                    while room_receiver.len() > 0 {
+                                        let _ = room_receiver.recv().await;
+                                    }
                                 }
                                 match err {
                                     tokio::sync::broadcast::error::RecvError::Closed => error!("Channel is already closed"),
-                                    tokio::sync::broadcast::error::RecvError::Lagged(lag) => warn!(lag, "Lagging behind"),
+                                    tokio::sync::broadcast::error::RecvError::Lagged(lag) => {
+                                        warn!(lag, "Lagging behind");
+                                        counter!("stecker_lagged_receivers_total", "source" => "data_room").increment(1);
+                                    },
                                 }
                             },
                         }
                     },
                     raw_msg = inbound_receiver.recv() => {
-                        match raw_msg {
-                            Ok(msg) => warn!(?msg, "Broadcasting message from subscriber will be ignored"),
-                            Err(_) => error!("Error while receiving inbound message"),
+                        match (raw_msg, &jam_sender, &crowd) {
+                            (Ok(_), Some(_), _) if last_jam_message.is_some_and(|last_jam_message| last_jam_message.elapsed() < MIN_JAM_MESSAGE_INTERVAL) => {
+                                debug!("Drop message of jam sender because of rate limit");
+                            },
+                            (Ok(msg), Some((jam, sender_name)), _) => {
+                                last_jam_message = Some(Instant::now());
+                                // the identity of the sender gets announced via the meta channel
+                                let announcement = MetaMessage::JamMessage {
+                                    sender: sender_name.clone(),
+                                    value: msg.clone().into(),
+                                }.encode();
+                                let _ = history.publish(jam.merge(sender_name, msg));
+                                let _ = meta_rx.send(announcement.clone());
+                                let _ = meta_reply.send(announcement);
+                            },
+                            (Ok(SteckerData::F32(value)), None, Some(crowd)) => crowd.contribute(listener_uuid, value),
+                            (Ok(msg), None, _) => warn!(?msg, "Broadcasting message from subscriber will be ignored"),
+                            (Err(_), _, _) => error!("Error while receiving inbound message"),
                         }
                     },
                     raw_meta_msg = meta_receiver.recv() => {
@@ -432,14 +1421,17 @@ impl DataBroadcastRoom {
                     },
                     raw_msg = meta_inbound_receiver.recv() => {
                         match raw_msg {
-                            Ok(meta_msg) => warn!(?meta_msg, "Meta message from subscriber will be ignored"),
+                            Ok(meta_msg) => listener_meta.handle(meta_msg),
                             Err(_) => error!("Error on receiving inbound meta message"),
                         }
                     },
                     _ = num_listeners_receiver.changed() => {
                         let cur_num_listeners = *num_listeners_receiver.borrow();
                         info!(cur_num_listeners, "Number of listeners changed");
-                        let _ = meta_channel.outbound.send(SteckerData::String(format!("Number of listeners: {cur_num_listeners}").to_string()));
+                        let _ = meta_channel.outbound.send(MetaMessage::ListenerCount {
+                            room: room_name.clone(),
+                            num_listeners: cur_num_listeners,
+                        }.encode());
                     },
                     _ = stop_receiver2.recv() => {
                         trace!("Stop consuming inbound messages now");
@@ -451,6 +1443,14 @@ impl DataBroadcastRoom {
                     }
                 };
             }
+            if let Some(crowd) = crowd {
+                crowd.leave(&listener_uuid);
+            }
+            if let Some((jam, sender_name)) = jam_sender {
+                if let Some(merged) = jam.leave(&sender_name) {
+                    let _ = history.publish(merged);
+                }
+            }
             let cur_num_listeners = *num_listeners2.borrow();
             let _ = num_listeners2.send(cur_num_listeners - 1);
         }.instrument(Span::current()));
@@ -461,15 +1461,34 @@ impl DataBroadcastRoom {
 impl From<&BroadcastRoom> for Room {
     fn from(value: &BroadcastRoom) -> Self {
         let meta = value.meta();
-        let room_type = match value {
-            BroadcastRoom::Data(data_room) => data_room.room_type.into(),
-            BroadcastRoom::Audio(_) => RoomType::Audio,
+        let (room_type, merge_policy, crowd) = match value {
+            BroadcastRoom::Data(data_room) => (
+                data_room.room_type.into(),
+                data_room.jam.as_ref().map(|jam| jam.policy),
+                data_room.crowd.is_some(),
+            ),
+            BroadcastRoom::Audio(_) => (RoomType::Audio, None, false),
         };
         Room {
             uuid: meta.uuid.to_string(),
             name: meta.name.clone(),
             num_listeners: *meta.num_listeners.subscribe().borrow(),
             room_type,
+            inactivity_timeout: meta
+                .lifetime
+                .inactivity_timeout
+                .as_secs()
+                .try_into()
+                .unwrap_or(i32::MAX),
+            remaining_lifetime: meta.lifetime.expires_at.map(|expires_at| {
+                expires_at
+                    .saturating_duration_since(Instant::now())
+                    .as_secs()
+                    .try_into()
+                    .unwrap_or(i32::MAX)
+            }),
+            merge_policy,
+            crowd,
         }
     }
 }
@@ -492,6 +1511,8 @@ impl AudioBroadcastRoom {
         offer: String,
         admin_password: String,
         room_events: Sender<RoomEvent>,
+        lifetime: RoomLifetime,
+        shutdown: Shutdown,
     ) -> anyhow::Result<AudioBroadcastRoomWithOffer> {
         let connection = SteckerWebRTCConnection::build_connection()
             .in_current_span()
@@ -505,9 +1526,15 @@ impl AudioBroadcastRoom {
         let meta_channel = connection.register_channel(&DataRoomInternalType::Meta);
         let (num_listeners_sender, num_listeners_receiver) = tokio::sync::watch::channel(0);
         let response_offer = connection.respond_to_offer(offer).in_current_span().await?;
+        // the meta channel delivers the feedback of the listeners to the sender
+        connection
+            .start_listening_for_data_channel()
+            .in_current_span()
+            .await;
 
         let audio_channel_tx = audio_channel.audio_channel_tx.clone();
         let connection_events = connection.connection_events.clone();
+        shutdown.close_on_shutdown(connection);
         let mut stop_consuming = audio_channel.reset_sender.subscribe();
         let seq_number_sender = audio_channel.sequence_number_sender.clone();
 
@@ -516,6 +1543,8 @@ impl AudioBroadcastRoom {
         let mut num_listeners_receiver2 = num_listeners_receiver.clone();
         let room_events2 = room_events.clone();
         let room_name2 = name.clone();
+        let rtp_packets = counter!("stecker_rtp_packets_total", "room" => name.clone());
+        let rtp_bytes = counter!("stecker_rtp_bytes_total", "room" => name.clone());
         tokio::spawn(async move {
                 let track = audio_track_receiver.recv().await.unwrap();
                 let local_track = Arc::new(TrackLocalStaticRTP::new(
@@ -534,6 +1563,8 @@ impl AudioBroadcastRoom {
                                 let _ = seq_number_sender.send(seq_number);
                                 // trace!(seq_number, "Currently sending");
                                 let _ = local_track.write_rtp(&rtp).await;
+                                rtp_packets.increment(1);
+                                rtp_bytes.increment(rtp.payload.len() as u64);
                             } else {
                                 error!("Failed to read track - stop consuming");
                                 break;
@@ -541,7 +1572,7 @@ impl AudioBroadcastRoom {
                        },
                        num = num_listeners_receiver2.changed() => {
                             if let Ok(_) = num {
-                                let _ = room_events2.send(RoomEvent::BroadcastRoomUserCount(room_name2.clone(), *num_listeners_receiver2.borrow()));
+                                let _ = room_events2.send(RoomEvent::BroadcastRoomUserCount(RoomType::Audio, room_name2.clone(), *num_listeners_receiver2.borrow()));
                             }
                        },
                        _ = stop_consuming.recv() => {
@@ -568,6 +1599,8 @@ impl AudioBroadcastRoom {
                     _num_listeners_receiver: num_listeners_receiver,
                     admin_password,
                     room_events,
+                    lifetime,
+                    shutdown,
                 },
             },
         });
@@ -577,7 +1610,7 @@ impl AudioBroadcastRoom {
     pub async fn join_room(&self, offer: &str) -> anyhow::Result<ResponseOffer> {
         trace!("Join room");
         let connection = SteckerWebRTCConnection::build_connection().await?;
-        let _meta_channel = connection.register_channel(&DataRoomInternalType::Meta);
+        let meta_channel = connection.register_channel(&DataRoomInternalType::Meta);
 
         let audio_track_receiver = self
             .stecker_audio_channel
@@ -591,6 +1624,39 @@ impl AudioBroadcastRoom {
                 trace!("Found an audio track");
                 let _ = connection.add_existing_audio_track(audio_track).await;
                 let response_offer = connection.respond_to_offer(offer.to_owned()).await?;
+                connection
+                    .start_listening_for_data_channel()
+                    .in_current_span()
+                    .await;
+                self.meta.shutdown.close_on_shutdown(connection.clone());
+
+                let mut listener_meta =
+                    ListenerMeta::new(RoomType::Audio, &self.meta, meta_channel.outbound.clone());
+                let mut meta_inbound = meta_channel.inbound.subscribe();
+                let mut meta_close = meta_channel.close.subscribe();
+                let mut room_meta = self.meta.meta_broadcast.subscribe();
+                tokio::spawn(
+                    async move {
+                        loop {
+                            tokio::select! {
+                                msg = meta_inbound.recv() => {
+                                    match msg {
+                                        Ok(msg) => listener_meta.handle(msg),
+                                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {},
+                                    }
+                                },
+                                msg = room_meta.recv() => {
+                                    if let Ok(msg) = msg {
+                                        let _ = meta_channel.outbound.send(msg);
+                                    }
+                                },
+                                _ = meta_close.recv() => break,
+                            }
+                        }
+                    }
+                    .in_current_span(),
+                );
 
                 let mut connection_events = connection.connection_events.subscribe();
                 let num_listeners = self.meta.num_listeners.clone();
@@ -646,6 +1712,7 @@ impl AudioBroadcastRoom {
             .listen_for_remote_audio_track()
             .in_current_span()
             .await;
+        self.meta.shutdown.close_on_shutdown(connection);
 
         let local_track = if let Some(track) = self
             .stecker_audio_channel
@@ -667,6 +1734,8 @@ impl AudioBroadcastRoom {
         let seq_number_sender = self.stecker_audio_channel.sequence_number_sender.clone();
         let mut seq_number_receiver = self.stecker_audio_channel.sequence_number_receiver.clone();
         let _ = *seq_number_receiver.borrow_and_update();
+        let rtp_packets = counter!("stecker_rtp_packets_total", "room" => self.meta.name.clone());
+        let rtp_bytes = counter!("stecker_rtp_bytes_total", "room" => self.meta.name.clone());
 
         tokio::spawn(
             async move {
@@ -686,6 +1755,8 @@ impl AudioBroadcastRoom {
                                 rtp.header.sequence_number = last_seq;
                                 let _ = seq_number_sender.send(last_seq);
                                 let _ = local_track.write_rtp(&rtp).await;
+                                rtp_packets.increment(1);
+                                rtp_bytes.increment(rtp.payload.len() as u64);
                             } else {
                                 error!("Failed to read track - stop consuming");
                                 break;
@@ -701,6 +1772,12 @@ impl AudioBroadcastRoom {
             .in_current_span(),
         );
 
+        let _ = self.meta.meta_broadcast.send(
+            MetaMessage::SenderChanged {
+                room: self.meta.name.clone(),
+            }
+            .encode(),
+        );
         Ok(response_offer)
     }
 }
@@ -716,8 +1793,303 @@ impl Into<RoomType> for DataRoomInternalType {
     }
 }
 
-#[derive(SimpleObject, Clone)]
+/// message of a chat room
+#[derive(SimpleObject, Clone, Debug)]
+pub struct ChatMessage {
+    pub text: String,
+    /// unix timestamp in milliseconds
+    pub timestamp: i64,
+}
+
+#[derive(SimpleObject, Clone, Serialize)]
 pub struct RoomCreationReply {
     pub offer: String,
     pub password: String,
 }
+
+/// room events a webhook can subscribe to
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum WebhookEvent {
+    RoomCreated,
+    /// the sender of a room has been replaced
+    RoomUpdated,
+    RoomDeleted,
+    RoomUserCount,
+}
+
+impl Display for WebhookEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            WebhookEvent::RoomCreated => write!(f, "roomCreated"),
+            WebhookEvent::RoomUpdated => write!(f, "roomUpdated"),
+            WebhookEvent::RoomDeleted => write!(f, "roomDeleted"),
+            WebhookEvent::RoomUserCount => write!(f, "roomUserCount"),
+        }
+    }
+}
+
+/// An URL which gets notified about room events via a signed POST request.
+#[derive(SimpleObject, Clone, Debug)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    /// events which get delivered to the webhook
+    pub events: Vec<WebhookEvent>,
+    /// key of the HMAC-SHA256 signature in the `X-Stecker-Signature` header
+    #[graphql(skip)]
+    pub secret: String,
+}
+
+impl Webhook {
+    pub fn subscribes_to(&self, event: &WebhookEvent) -> bool {
+        self.events.contains(event)
+    }
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct WebhookCreationReply {
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+/// version and capabilities of the server, used by clients to check
+/// if they are able to talk to the server
+#[derive(SimpleObject, Clone, Serialize)]
+pub struct ServerInfo {
+    pub api_version: String,
+    pub room_types: Vec<RoomType>,
+    /// codecs which can be used within audio rooms
+    pub audio_codecs: Vec<String>,
+    /// if ICE candidates can be exchanged after the offer has been sent
+    pub trickle_ice: bool,
+    /// optional features, see [`shared::models::features`]
+    pub features: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn least_listeners_prefers_first_room_on_tie() {
+        let counter = AtomicUsize::new(0);
+        let dispatcher = DispatcherType::LeastListeners;
+        assert_eq!(dispatcher.choose_index(&[3, 1, 1], 0, &counter), Some(1));
+        assert_eq!(dispatcher.choose_index(&[], 0, &counter), None);
+    }
+
+    #[test]
+    fn round_robin_wraps_around_changing_rooms() {
+        let counter = AtomicUsize::new(0);
+        let dispatcher = DispatcherType::RoundRobin;
+        let chosen: Vec<_> = (0..4)
+            .map(|_| dispatcher.choose_index(&[0, 0, 0], 0, &counter))
+            .collect();
+        assert_eq!(chosen, vec![Some(0), Some(1), Some(2), Some(0)]);
+        // the position is kept if a room disappears
+        assert_eq!(dispatcher.choose_index(&[0, 0], 0, &counter), Some(0));
+        assert_eq!(dispatcher.choose_index(&[0, 0], 0, &counter), Some(1));
+        assert_eq!(dispatcher.choose_index(&[], 0, &counter), None);
+    }
+
+    #[test]
+    fn max_listeners_skips_full_rooms() {
+        let counter = AtomicUsize::new(0);
+        let dispatcher = DispatcherType::MaxListeners;
+        assert_eq!(dispatcher.choose_index(&[2, 3, 1], 2, &counter), Some(2));
+        assert_eq!(dispatcher.choose_index(&[2, 2], 2, &counter), None);
+    }
+
+    fn crowd(histogram_bins: i32) -> CrowdAggregator {
+        CrowdAggregator::new(
+            CrowdInput {
+                histogram_bins: Some(histogram_bins),
+                ..Default::default()
+            },
+            &RoomType::Float,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn crowd_aggregates_without_contributions() {
+        let aggregates = crowd(4).aggregates();
+        assert_eq!(aggregates.count, 0);
+        assert_eq!(aggregates.mean, None);
+        assert_eq!(aggregates.median, None);
+        assert_eq!(aggregates.histogram, vec![0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn crowd_aggregates_median() {
+        let crowd = crowd(4);
+        for value in [0.9, 0.1, 0.5] {
+            crowd.contribute(Uuid::new_v4(), value);
+        }
+        assert_eq!(crowd.aggregates().median, Some(0.5));
+
+        crowd.contribute(Uuid::new_v4(), 0.7);
+        let aggregates = crowd.aggregates();
+        assert_eq!(aggregates.count, 4);
+        assert!((aggregates.median.unwrap() - 0.6).abs() < 1e-6);
+        assert!((aggregates.mean.unwrap() - 0.55).abs() < 1e-6);
+    }
+
+    #[test]
+    fn crowd_aggregates_histogram_edges() {
+        let crowd = crowd(4);
+        // values on the upper edge and outside of the range end up in the outer bins
+        for value in [-1.0, 0.0, 0.25, 0.5, 1.0, 2.0] {
+            crowd.contribute(Uuid::new_v4(), value);
+        }
+        assert_eq!(crowd.aggregates().histogram, vec![2, 1, 1, 2]);
+    }
+
+    #[test]
+    fn crowd_keeps_most_recent_value_of_listener() {
+        let crowd = crowd(2);
+        let listener = Uuid::new_v4();
+        crowd.contribute(listener, 0.1);
+        crowd.contribute(listener, 0.9);
+        crowd.contribute(Uuid::new_v4(), f32::NAN);
+        let aggregates = crowd.aggregates();
+        assert_eq!(aggregates.count, 1);
+        assert_eq!(aggregates.mean, Some(0.9));
+
+        crowd.leave(&listener);
+        assert_eq!(crowd.aggregates().count, 0);
+    }
+
+    #[test]
+    fn crowd_rejects_invalid_settings() {
+        assert!(CrowdAggregator::new(CrowdInput::default(), &RoomType::Chat).is_err());
+        assert!(CrowdAggregator::new(
+            CrowdInput {
+                min: Some(1.0),
+                max: Some(0.0),
+                ..Default::default()
+            },
+            &RoomType::Float
+        )
+        .is_err());
+    }
+
+    fn jam_mixer(merge_policy: MergePolicy) -> JamMixer {
+        JamMixer::new(
+            JamInput {
+                merge_policy,
+                password: None,
+            },
+            &RoomType::Float,
+        )
+        .unwrap()
+    }
+
+    fn value(data: SteckerData) -> f32 {
+        match data {
+            SteckerData::F32(value) => value,
+            SteckerData::String(text) => panic!("Expected a float, got {text}"),
+        }
+    }
+
+    #[test]
+    fn jam_merges_most_recent_values() {
+        let jam = jam_mixer(MergePolicy::Sum);
+        assert_eq!(value(jam.merge("a", SteckerData::F32(1.0))), 1.0);
+        assert_eq!(value(jam.merge("b", SteckerData::F32(2.0))), 3.0);
+        assert_eq!(value(jam.merge("a", SteckerData::F32(4.0))), 6.0);
+
+        let jam = jam_mixer(MergePolicy::Max);
+        jam.merge("a", SteckerData::F32(1.0));
+        assert_eq!(value(jam.merge("b", SteckerData::F32(-1.0))), 1.0);
+
+        let jam = jam_mixer(MergePolicy::LastWriterWins);
+        jam.merge("a", SteckerData::F32(1.0));
+        assert_eq!(value(jam.merge("b", SteckerData::F32(-1.0))), -1.0);
+    }
+
+    #[test]
+    fn jam_leave_updates_merged_value() {
+        let jam = jam_mixer(MergePolicy::Mean);
+        jam.merge("a", SteckerData::F32(1.0));
+        jam.merge("b", SteckerData::F32(3.0));
+        assert_eq!(jam.leave("b").map(value), Some(1.0));
+        // the last sender leaving does not change the value of the room
+        assert!(jam.leave("a").is_none());
+        assert!(jam.leave("unknown").is_none());
+
+        let jam = jam_mixer(MergePolicy::LastWriterWins);
+        jam.merge("a", SteckerData::F32(1.0));
+        jam.merge("b", SteckerData::F32(3.0));
+        assert!(jam.leave("b").is_none());
+    }
+
+    #[test]
+    fn jam_sender_names_stay_taken_until_leave() {
+        let jam = jam_mixer(MergePolicy::Sum);
+        let participant = || JamParticipantInput {
+            sender_name: Some("alice".to_string()),
+            password: None,
+        };
+        assert_eq!(
+            jam.sender_name(participant()).unwrap(),
+            Some("alice".to_string())
+        );
+        assert!(jam.sender_name(participant()).is_err());
+        jam.leave("alice");
+        assert!(jam.sender_name(participant()).is_ok());
+
+        assert!(jam
+            .sender_name(JamParticipantInput {
+                sender_name: Some(JAM_CREATOR.to_string()),
+                password: None,
+            })
+            .is_err());
+    }
+
+    fn history_values(history: &RoomHistory, n: usize) -> Vec<f32> {
+        history
+            .last(n)
+            .into_iter()
+            .map(|entry| value(entry.data))
+            .collect()
+    }
+
+    fn room_history(capacity: usize) -> RoomHistory {
+        let (broadcast, _) = tokio::sync::broadcast::channel(SERVER_ROOM_CAPACITY);
+        RoomHistory::new(capacity, broadcast)
+    }
+
+    #[test]
+    fn room_history_returns_last_entries_oldest_first() {
+        let history = room_history(3);
+        assert!(history.last(2).is_empty());
+        for i in 0..5 {
+            let _ = history.publish(SteckerData::F32(i as f32));
+        }
+        assert_eq!(history_values(&history, 2), vec![3.0, 4.0]);
+        // older entries than the capacity got dropped
+        assert_eq!(history_values(&history, 10), vec![2.0, 3.0, 4.0]);
+        assert!(history.last(0).is_empty());
+    }
+
+    #[test]
+    fn room_history_without_capacity_stays_empty() {
+        let history = room_history(0);
+        let _ = history.publish(SteckerData::F32(1.0));
+        assert!(history.last(1).is_empty());
+    }
+
+    #[test]
+    fn room_history_subscribers_receive_messages_after_the_replay() {
+        let history = room_history(3);
+        let _ = history.publish(SteckerData::F32(1.0));
+        let (replay, mut receiver) = history.subscribe(3);
+        let _ = history.publish(SteckerData::F32(2.0));
+
+        let replayed: Vec<f32> = replay.into_iter().map(|entry| value(entry.data)).collect();
+        assert_eq!(replayed, vec![1.0]);
+        assert_eq!(value(receiver.try_recv().unwrap()), 2.0);
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -14,6 +14,7 @@ use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite};
 use tower::Service;
 use tracing::{debug, error, instrument, trace, Instrument};
 
+use crate::metrics_service::OscConnectionGuard;
 use crate::models::{DispatcherType, RoomDispatcherInput, RoomType};
 use crate::state::AppState;
 
@@ -23,9 +24,10 @@ impl TryFrom<OscMessage> for RoomDispatcherInput {
     #[instrument(skip_all)]
     fn try_from(message: OscMessage) -> Result<RoomDispatcherInput, Self::Error> {
         match message.args.len() {
-            7 => Ok(RoomDispatcherInput {
+            7..=9 => Ok(RoomDispatcherInput {
                 name: message.args[0].clone().string().ok_or(())?,
                 admin_password: message.args[1].clone().string(),
+                admin_password_hash: None,
                 rule: message.args[2].clone().string().ok_or(())?,
                 room_type: RoomType::Audio,
                 dispatcher_type: DispatcherType::try_from(
@@ -35,6 +37,8 @@ impl TryFrom<OscMessage> for RoomDispatcherInput {
                 timeout: message.args[3].clone().int().ok_or(())?,
                 return_room_prefix: message.args[4].clone().string(),
                 add_random_postfix: message.args[6].clone().int().unwrap_or(0) >= 1,
+                max_listeners: message.args.get(7).and_then(|arg| arg.clone().int()),
+                sticky_timeout: message.args.get(8).and_then(|arg| arg.clone().int()),
             }),
             _ => {
                 trace!("Invaild length of room dispatch OSC message");
@@ -85,11 +89,13 @@ impl OscProcessor {
 
 #[instrument(skip(socket, state))]
 pub async fn handle_osc_client(socket: TcpStream, addr: SocketAddr, state: Arc<AppState>) {
+    let _connection_guard = OscConnectionGuard::register();
     let (reader, writer) = socket.into_split();
     let mut framed_reader = FramedRead::new(reader, OscDecoder);
     let mut framed_writer = FramedWrite::new(writer, OscEncoder);
 
-    let (tx_outgoing_osc, mut rx_outgoing_osc) = mpsc::channel::<OscPacket>(16);
+    let (tx_outgoing_osc, mut rx_outgoing_osc) =
+        mpsc::channel::<OscPacket>(state.config.limits.osc_queue_capacity);
     let tx_outgoing_ping_osc = tx_outgoing_osc.clone();
     let mut room_events_rx = state.room_events.subscribe();
 
@@ -0,0 +1,77 @@
+use std::{path::Path, sync::Arc};
+
+use anyhow::{bail, Context};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, instrument, warn};
+
+use crate::{event_service::RoomEvent, models::RoomDispatcherInput, state::AppState};
+
+/// creates the dispatchers which have been stored in the file
+#[instrument(skip(state))]
+pub async fn restore_dispatchers(state: &AppState, path: &Path) -> anyhow::Result<()> {
+    if !path.exists() {
+        info!("No stored dispatchers found");
+        return Ok(());
+    }
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Could not read dispatchers file {path:?}"))?;
+    let dispatchers: Vec<RoomDispatcherInput> = serde_json::from_str(&content)
+        .with_context(|| format!("Could not parse dispatchers file {path:?}"))?;
+
+    let mut num_failed = 0;
+    for dispatcher in dispatchers {
+        let name = dispatcher.name.clone();
+        if let Err(err) = state.create_dispatcher(dispatcher).await {
+            warn!(?err, name, "Could not restore dispatcher");
+            num_failed += 1;
+        }
+    }
+    if num_failed > 0 {
+        bail!("{num_failed} dispatchers of {path:?} could not be restored");
+    }
+    Ok(())
+}
+
+async fn store_dispatchers(state: &AppState, path: &Path) -> anyhow::Result<()> {
+    let dispatchers: Vec<RoomDispatcherInput> = state
+        .room_dispatchers
+        .read()
+        .await
+        .values()
+        .map(RoomDispatcherInput::from)
+        .collect();
+    let content = serde_json::to_string_pretty(&dispatchers)?;
+    // the file gets replaced at once, so it stays intact if the server stops while writing
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    tokio::fs::write(&temp_path, content)
+        .await
+        .with_context(|| format!("Could not write dispatchers file {temp_path:?}"))?;
+    tokio::fs::rename(&temp_path, path)
+        .await
+        .with_context(|| format!("Could not replace dispatchers file {path:?}"))
+}
+
+/// writes all dispatchers to the file whenever a dispatcher changes,
+/// only the hashes of their admin passwords get stored
+#[instrument(skip(state))]
+pub async fn persist_dispatchers(state: Arc<AppState>, path: &Path) {
+    let mut room_events = state.room_events.subscribe();
+    loop {
+        match room_events.recv().await {
+            Ok(
+                RoomEvent::RoomDispatcherCreated(_)
+                | RoomEvent::RoomDispatcherUpdated(_)
+                | RoomEvent::RoomDispatcherDeleted(_),
+            )
+            | Err(RecvError::Lagged(_)) => {
+                if let Err(err) = store_dispatchers(&state, path).await {
+                    error!(?err, "Failed to store dispatchers");
+                }
+            }
+            Ok(_) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
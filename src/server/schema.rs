@@ -1,21 +1,18 @@
-use std::{sync::Arc, time::Duration};
+use std::sync::Arc;
 
 use crate::{
-    event_service::RoomEvent,
+    logging,
     models::{
-        AudioBroadcastRoom, BroadcastRoom, DataBroadcastRoom, Room, RoomCreationReply,
-        RoomDispatcher, RoomDispatcherInput, RoomType,
+        ChatMessage, CrowdInput, JamInput, JamParticipantInput, Room, RoomCreationReply,
+        RoomDispatcher, RoomDispatcherInput, RoomDispatcherUpdate, RoomType, ServerInfo, Webhook,
+        WebhookCreationReply, WebhookEvent,
     },
-    state::RoomMapTrait,
 };
-use rand::distributions::{Alphanumeric, DistString};
 
-use anyhow::anyhow;
 use shared::models::API_VERSION;
-use tokio::{sync::RwLock, time::sleep};
 
 use async_graphql::{Context, Object};
-use tracing::{info, instrument, trace, Instrument, Span};
+use tracing::{info, instrument};
 use uuid::Uuid;
 
 use crate::AppState;
@@ -28,14 +25,31 @@ impl Query {
         API_VERSION.to_string()
     }
 
+    /// version and capabilities of the server
+    async fn server_info<'a>(&self, ctx: &Context<'a>) -> ServerInfo {
+        ctx.data_unchecked::<Arc<AppState>>().server_info()
+    }
+
     async fn rooms<'a>(&self, ctx: &Context<'a>, room_type: RoomType) -> Vec<Room> {
         let state = ctx.data_unchecked::<Arc<AppState>>();
 
-        match room_type {
-            RoomType::Float => state.float_rooms.get_rooms().await,
-            RoomType::Chat => state.chat_rooms.get_rooms().await,
-            RoomType::Audio => state.audio_rooms.get_rooms().await,
-        }
+        state.get_rooms(&room_type).await
+    }
+
+    /// recent messages of a chat room, oldest first
+    async fn chat_messages<'a>(
+        &self,
+        ctx: &Context<'a>,
+        room: String,
+        #[graphql(desc = "Number of most recent messages, all kept messages if not set")]
+        last: Option<i32>,
+    ) -> anyhow::Result<Vec<ChatMessage>> {
+        let state = ctx.data_unchecked::<Arc<AppState>>();
+        let last = match last {
+            Some(last) => usize::try_from(last)?,
+            None => usize::MAX,
+        };
+        state.chat_messages(&room, last).await
     }
 
     async fn room_dispatchers<'a>(&self, ctx: &Context<'a>) -> Vec<RoomDispatcher> {
@@ -48,20 +62,115 @@ impl Query {
             .map(|x| x.clone())
             .collect()
     }
+
+    async fn dispatcher<'a>(&self, ctx: &Context<'a>, name: String) -> Option<RoomDispatcher> {
+        let state = ctx.data_unchecked::<Arc<AppState>>();
+        state.room_dispatchers.read().await.get(&name).cloned()
+    }
+
+    /// the currently active `RUST_LOG`-style log filter
+    async fn log_filter<'a>(
+        &self,
+        ctx: &Context<'a>,
+        admin_password: String,
+    ) -> anyhow::Result<String> {
+        let state = ctx.data_unchecked::<Arc<AppState>>();
+        state.check_admin_password(&admin_password)?;
+        logging::current_filter()
+    }
+
+    async fn webhooks<'a>(
+        &self,
+        ctx: &Context<'a>,
+        admin_password: String,
+    ) -> anyhow::Result<Vec<Webhook>> {
+        let state = ctx.data_unchecked::<Arc<AppState>>();
+        state.check_admin_password(&admin_password)?;
+        Ok(state.webhooks.read().await.values().cloned().collect())
+    }
 }
 
 pub struct Mutation;
 
 #[Object]
 impl Mutation {
-    #[instrument(skip_all, parent = None)]
+    /// replaces the log filter at runtime, e.g. with `error,server=debug`
+    #[instrument(skip(self, ctx, admin_password), err)]
+    async fn set_log_filter<'a>(
+        &self,
+        ctx: &Context<'a>,
+        admin_password: String,
+        filter: String,
+    ) -> anyhow::Result<String> {
+        let state = ctx.data_unchecked::<Arc<AppState>>();
+        state.check_admin_password(&admin_password)?;
+        logging::set_filter(&filter)
+    }
+
+    /// registers an URL which gets notified about room events - the payload gets signed
+    /// with the returned secret, which is randomly generated if none is given
+    #[instrument(skip(self, ctx, admin_password, secret), err)]
+    async fn create_webhook<'a>(
+        &self,
+        ctx: &Context<'a>,
+        admin_password: String,
+        url: String,
+        events: Vec<WebhookEvent>,
+        secret: Option<String>,
+    ) -> anyhow::Result<WebhookCreationReply> {
+        let state = ctx.data_unchecked::<Arc<AppState>>();
+        state.check_admin_password(&admin_password)?;
+        state.create_webhook(url, events, secret).await
+    }
+
+    #[instrument(skip(self, ctx, admin_password), err)]
+    async fn delete_webhook<'a>(
+        &self,
+        ctx: &Context<'a>,
+        admin_password: String,
+        id: String,
+    ) -> anyhow::Result<bool> {
+        let state = ctx.data_unchecked::<Arc<AppState>>();
+        state.check_admin_password(&admin_password)?;
+        Ok(state.delete_webhook(&id).await)
+    }
+
+    /// sends a notice via the meta channel to the senders and listeners
+    /// of all rooms and returns the number of rooms
+    #[instrument(skip(self, ctx, admin_password), err)]
+    async fn send_server_notice<'a>(
+        &self,
+        ctx: &Context<'a>,
+        admin_password: String,
+        message: String,
+    ) -> anyhow::Result<usize> {
+        let state = ctx.data_unchecked::<Arc<AppState>>();
+        state.check_admin_password(&admin_password)?;
+        Ok(state.send_server_notice(message).await)
+    }
+
+    /// broadcasts a message to the listeners of a chat room,
+    /// requires the password of the room
+    #[instrument(skip(self, ctx, text, password), err)]
+    async fn send_chat_message<'a>(
+        &self,
+        ctx: &Context<'a>,
+        room: String,
+        text: String,
+        password: String,
+    ) -> anyhow::Result<ChatMessage> {
+        let state = ctx.data_unchecked::<Arc<AppState>>();
+        state.send_chat_message(&room, text, &password).await
+    }
+
+    #[instrument(skip_all)]
     async fn reset_rooms<'a>(&self, ctx: &Context<'a>) -> f32 {
         ctx.data_unchecked::<Arc<AppState>>().reset_rooms().await;
         info!("Resetted rooms");
         0.
     }
 
-    #[instrument(skip(self, ctx, offer, password), fields(connection_uuid), parent = None, err)]
+    #[instrument(skip(self, ctx, offer, password, jam), fields(connection_uuid), err)]
     async fn create_room<'a>(
         &self,
         ctx: &Context<'a>,
@@ -69,143 +178,43 @@ impl Mutation {
         offer: String,
         room_type: RoomType,
         password: Option<String>,
+        #[graphql(
+            desc = "Seconds without a signal from the sender after which the room gets deleted"
+        )]
+        inactivity_timeout: Option<i32>,
+        #[graphql(desc = "Unix timestamp (in seconds) at which the room gets deleted")]
+        expires_at: Option<i64>,
+        #[graphql(
+            desc = "Number of recent messages new listeners of a data room receive on joining, defaults to the last value for float rooms"
+        )]
+        replay_length: Option<i32>,
+        #[graphql(desc = "Turns a data room into a jam room, in which listeners may send as well")]
+        jam: Option<JamInput>,
+        #[graphql(
+            desc = "Turns a float room into a crowd room, in which the values of all listeners get aggregated"
+        )]
+        crowd: Option<CrowdInput>,
     ) -> anyhow::Result<RoomCreationReply> {
         let connection_uuid = Uuid::new_v4();
         tracing::Span::current().record("connection_uuid", connection_uuid.to_string());
 
         let state = ctx.data_unchecked::<Arc<AppState>>();
-
-        if state.room_exists(&name, &room_type).await {
-            if let Some(user_provided_password) = password {
-                if state
-                    .room_password_match(&name, &room_type, &user_provided_password)
-                    .await
-                {
-                    trace!("Matched password of existing room");
-                    let offer = state
-                        .replace_sender(&name, &room_type, &user_provided_password, &offer)
-                        .await?;
-
-                    let _ = state
-                        .room_events
-                        .send(RoomEvent::BroadcastRoomUpdated(name.clone()));
-
-                    return Ok(RoomCreationReply {
-                        offer,
-                        password: user_provided_password,
-                    });
-                }
-            };
-            return Err(anyhow!("The room name is already taken."));
-        }
-
-        let room_password: String = if let Some(user_provided_password) = password {
-            user_provided_password
-        } else {
-            Alphanumeric.sample_string(&mut rand::thread_rng(), 8)
-        };
-
-        let name2 = name.clone();
-        let name3 = name.clone();
-        let room_password2 = room_password.clone();
-        match room_type {
-            RoomType::Float | RoomType::Chat => {
-                let result = DataBroadcastRoom::create_room(
-                    name,
-                    offer,
-                    room_type.into(),
-                    room_password,
-                    state.room_events.clone(),
-                )
-                .instrument(Span::current())
-                .await?;
-                {
-                    let mut room_lock = match room_type {
-                        RoomType::Float => {
-                            info!("Created a float room");
-                            state.float_rooms.map.write().await
-                        }
-                        RoomType::Chat => {
-                            info!("Created a chat room");
-                            state.chat_rooms.map.write().await
-                        }
-                        RoomType::Audio => {
-                            todo!("This can not happen - can we inherit the types from above?")
-                        }
-                    };
-                    let room = Arc::new(RwLock::new(BroadcastRoom::Data(result.broadcast_room)));
-                    room_lock.insert(name2, room.clone());
-                }
-                Ok(RoomCreationReply {
-                    offer: result.offer,
-                    password: room_password2,
-                })
-            }
-            RoomType::Audio => {
-                let result = AudioBroadcastRoom::create_room(
-                    name,
-                    offer,
-                    room_password,
-                    state.room_events.clone(),
-                )
-                .in_current_span()
-                .await?;
-                {
-                    let mut room_lock = match room_type {
-                        RoomType::Audio => state.audio_rooms.map.write().await,
-                        _ => {
-                            todo!("This can not happen - can we inherit the types from above?")
-                        }
-                    };
-
-                    let mut stream_sequence_number = result
-                        .audio_broadcast_room
-                        .stecker_audio_channel
-                        .sequence_number_receiver
-                        .clone();
-
-                    let room = Arc::new(RwLock::new(BroadcastRoom::Audio(
-                        result.audio_broadcast_room,
-                    )));
-                    let name3 = name2.clone();
-                    let name4 = name2.clone();
-                    let room_events_sender = state.room_events.clone();
-                    room_lock.insert(name2, room.clone());
-
-                    let audio_room = state.audio_rooms.map.clone();
-
-                    tokio::spawn(async move {
-                        loop {
-                            tokio::select! {
-                                _ = stream_sequence_number.changed() => {},
-                                _ = tokio::time::sleep(Duration::from_secs(30)) => {
-                                    info!("Timeout for not receiving any package from the sender");
-                                    break;
-                                }
-                            }
-                        }
-                        let _ = room_events_sender.send(RoomEvent::BroadcastRoomDeleted(name4.clone()));
-                        let mut audio_room_mutex_lock = audio_room.write().await;
-                        audio_room_mutex_lock.remove(&name3);
-                        info!("Cleared room");
-                    }
-                    .in_current_span(),);
-                }
-                info!("Created an audio room");
-
-                let _ = state
-                    .room_events
-                    .send(RoomEvent::BroadcastRoomCreated(name3.clone()));
-
-                Ok(RoomCreationReply {
-                    offer: result.offer,
-                    password: room_password2,
-                })
-            }
-        }
+        state
+            .create_room(
+                name,
+                offer,
+                room_type,
+                password,
+                inactivity_timeout,
+                expires_at,
+                replay_length,
+                jam,
+                crowd,
+            )
+            .await
     }
 
-    #[instrument(skip(self, ctx, dispatcher), fields(dispatcher_name=dispatcher.name), parent = None, err)]
+    #[instrument(skip(self, ctx, dispatcher), fields(dispatcher_name=dispatcher.name), err)]
     async fn create_dispatcher<'a>(
         &self,
         ctx: &Context<'a>,
@@ -216,61 +225,41 @@ impl Mutation {
         state.create_dispatcher(dispatcher).await
     }
 
-    #[instrument(skip(self, ctx, offer), fields(connection_uuid), parent = None, err)]
+    #[instrument(skip(self, ctx, name, admin_password, update), fields(dispatcher_name=name), err)]
+    async fn update_dispatcher<'a>(
+        &self,
+        ctx: &Context<'a>,
+        name: String,
+        admin_password: String,
+        update: RoomDispatcherUpdate,
+    ) -> anyhow::Result<RoomDispatcher> {
+        let state = ctx.data_unchecked::<Arc<AppState>>();
+
+        state
+            .update_dispatcher(&name, &admin_password, update)
+            .await
+    }
+
+    #[instrument(skip(self, ctx, offer, jam), fields(connection_uuid), err)]
     async fn join_room<'a>(
         &self,
         ctx: &Context<'a>,
         name: String,
         offer: String,
         room_type: RoomType,
+        #[graphql(desc = "Allows the listener to send into a jam room")] jam: Option<
+            JamParticipantInput,
+        >,
     ) -> anyhow::Result<String> {
         let connection_uuid = Uuid::new_v4();
         tracing::Span::current().record("connection_uuid", connection_uuid.to_string());
 
         let state = ctx.data_unchecked::<Arc<AppState>>();
-
-        match room_type {
-            RoomType::Float => match state.float_rooms.map.read().await.get(&name) {
-                Some(broadcast_room) => Ok(broadcast_room
-                    .read()
-                    .await
-                    .join_room(&offer)
-                    .instrument(Span::current())
-                    .await?),
-                None => Err(anyhow!("No such room {name}")),
-            },
-            RoomType::Chat => match state.chat_rooms.map.read().await.get(&name) {
-                Some(broadcast_room) => Ok(broadcast_room
-                    .read()
-                    .await
-                    .join_room(&offer)
-                    .instrument(Span::current())
-                    .await?),
-                None => Err(anyhow!("No such room {name}")),
-            },
-            RoomType::Audio => match state.audio_rooms.map.read().await.get(&name) {
-                Some(broadcast_room) => Ok(broadcast_room
-                    .read()
-                    .await
-                    .join_room(&offer)
-                    .instrument(Span::current())
-                    .await?),
-                None => Err(anyhow!("No such room {name}")),
-            },
-        }
+        state.join_room(&name, &offer, &room_type, jam).await
     }
 
     async fn access_dispatcher<'a>(&self, ctx: &Context<'a>, name: String) -> anyhow::Result<Room> {
         let state = ctx.data_unchecked::<Arc<AppState>>();
-
-        if let Some(dispatcher) = state.room_dispatchers.read().await.get(&name) {
-            match dispatcher.room_type {
-                RoomType::Float => todo!(),
-                RoomType::Chat => todo!(),
-                RoomType::Audio => state.audio_rooms.get_room(dispatcher).await,
-            }
-        } else {
-            Err(anyhow!("Could not find a dispatcher with the given name"))
-        }
+        state.access_dispatcher(&name).await
     }
 }
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use shared::connections::{ConnectionEvent, SteckerWebRTCConnection};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use tracing::{info, trace, warn, Instrument};
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+
+/// time which is granted to the peer connections to close themselves
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Coordinates the graceful shutdown of the server.
+/// Clones share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct Shutdown {
+    /// cancelled as soon as the shutdown starts - no new rooms are accepted afterwards
+    draining: CancellationToken,
+    /// cancelled after the drain period - all peer connections get closed
+    closing: CancellationToken,
+    connections: TaskTracker,
+}
+
+impl Shutdown {
+    pub fn is_draining(&self) -> bool {
+        self.draining.is_cancelled()
+    }
+
+    /// returns an error if the server does not accept new connections anymore
+    pub fn ensure_running(&self) -> anyhow::Result<()> {
+        if self.is_draining() {
+            Err(anyhow!("The server is shutting down."))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// resolves once all peer connections should be closed
+    pub async fn closing(&self) {
+        self.closing.cancelled().await
+    }
+
+    /// closes the connection once the drain period of a shutdown has passed
+    pub fn close_on_shutdown(&self, connection: SteckerWebRTCConnection) {
+        let closing = self.closing.clone();
+        let mut connection_events = connection.connection_events.subscribe();
+        self.connections.spawn(
+            async move {
+                loop {
+                    tokio::select! {
+                        _ = closing.cancelled() => {
+                            trace!("Close connection because of shutdown");
+                            let _ = connection.close().await;
+                            break;
+                        },
+                        event = connection_events.recv() => {
+                            match event {
+                                Ok(ConnectionEvent::NewPeerConnectionState(
+                                    RTCPeerConnectionState::Closed | RTCPeerConnectionState::Failed,
+                                ))
+                                | Err(RecvError::Closed) => break,
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+            }
+            .in_current_span(),
+        );
+    }
+
+    /// stops accepting new rooms, waits for the drain period
+    /// and closes all peer connections afterwards
+    pub async fn drain(&self, drain_period: Duration) {
+        self.draining.cancel();
+        info!(?drain_period, "Start draining connections");
+        tokio::time::sleep(drain_period).await;
+
+        info!(
+            num_connections = self.connections.len(),
+            "Close all connections"
+        );
+        self.closing.cancel();
+        self.connections.close();
+        if tokio::time::timeout(CLOSE_TIMEOUT, self.connections.wait())
+            .await
+            .is_err()
+        {
+            warn!("Not all connections could be closed in time");
+        }
+    }
+}
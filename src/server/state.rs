@@ -1,48 +1,202 @@
 use futures::stream::{self, StreamExt};
-use std::{collections::HashMap, future::Future, path::PathBuf, sync::Arc, time::Duration};
-use tracing::{info, Instrument};
+use rand::distributions::{Alphanumeric, DistString};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    future::Future,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, trace, Instrument, Span};
+use uuid::Uuid;
 
+use async_graphql::MaybeUndefined;
+use axum::extract::FromRef;
+use axum_extra::extract::cookie::Key;
+use metrics::counter;
 use minijinja;
-use tokio::{sync::RwLock, time::sleep};
+use regex::Regex;
+use shared::models::{features, MetaMessage, SteckerData, API_VERSION};
+use tokio::{
+    sync::{broadcast::error::RecvError, RwLock},
+    time::{interval, sleep, sleep_until},
+};
 
 use crate::{
+    assets::template_environment,
+    config::Config,
     event_service::RoomEvent,
-    models::{BroadcastRoom, Room, RoomDispatcher, RoomDispatcherInput, RoomType},
+    health::Readiness,
+    models::{
+        AudioBroadcastRoom, BroadcastRoom, ChatMessage, CrowdAggregator, CrowdInput,
+        DataBroadcastRoom, JamInput, JamMixer, JamParticipantInput, ReturnRoom, Room,
+        RoomCreationReply, RoomDispatcher, RoomDispatcherInput, RoomDispatcherUpdate, RoomHistory,
+        RoomLifetime, RoomType, ServerInfo, Webhook, WebhookCreationReply, WebhookEvent,
+    },
+    shutdown::Shutdown,
 };
 
+/// resolves once a room has reached its expiry, never if it does not expire
+async fn room_expiry(expires_at: Option<Instant>) {
+    match expires_at {
+        Some(expires_at) => sleep_until(expires_at.into()).await,
+        None => futures::future::pending().await,
+    }
+}
+
+/// failures of [`AppState`] operations which clients need to tell apart from invalid requests
+#[derive(Debug)]
+pub enum StateError {
+    NotFound(String),
+    NotSupported(String),
+}
+
+impl Display for StateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StateError::NotFound(message) | StateError::NotSupported(message) => {
+                write!(f, "{message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+/// upper bound for the number of messages replayed to new listeners of a data room
+const MAX_REPLAY_LENGTH: usize = 256;
+
+/// upper bound for messages sent via [`AppState::send_chat_message`]
+const MAX_CHAT_MESSAGE_LENGTH: usize = 4096;
+
+/// a reserved return room which has not been created by then can be taken by others
+const RETURN_ROOM_RESERVATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// default lifetimes of rooms, configurable on startup of the server
+#[derive(Debug, Clone)]
+pub struct RoomTimeouts {
+    pub audio_inactivity: Duration,
+    pub float_inactivity: Duration,
+    pub chat_inactivity: Duration,
+    /// upper bound for the lifetime of any room
+    pub max_lifetime: Option<Duration>,
+}
+
+impl Default for RoomTimeouts {
+    fn default() -> Self {
+        Self {
+            audio_inactivity: Duration::from_secs(30),
+            float_inactivity: Duration::from_secs(600),
+            chat_inactivity: Duration::from_secs(600),
+            max_lifetime: None,
+        }
+    }
+}
+
+impl RoomTimeouts {
+    /// combines the server defaults with the values requested for a new room,
+    /// where `expires_at` is a unix timestamp in seconds
+    pub fn room_lifetime(
+        &self,
+        room_type: &RoomType,
+        inactivity_timeout: Option<i32>,
+        expires_at: Option<i64>,
+    ) -> anyhow::Result<RoomLifetime> {
+        let inactivity_timeout = match inactivity_timeout {
+            Some(timeout) => Duration::from_secs(timeout.try_into()?),
+            None => match room_type {
+                RoomType::Float => self.float_inactivity,
+                RoomType::Chat => self.chat_inactivity,
+                RoomType::Audio => self.audio_inactivity,
+            },
+        };
+
+        let now = Instant::now();
+        let requested_expiry = match expires_at {
+            Some(timestamp) => {
+                let unix_now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+                let seconds_left = u64::try_from(timestamp)?.saturating_sub(unix_now);
+                Some(
+                    now.checked_add(Duration::from_secs(seconds_left))
+                        .ok_or_else(|| anyhow::anyhow!("expiresAt is too far in the future"))?,
+                )
+            }
+            None => None,
+        };
+        let max_expiry = self
+            .max_lifetime
+            .and_then(|max_lifetime| now.checked_add(max_lifetime));
+
+        Ok(RoomLifetime {
+            inactivity_timeout,
+            expires_at: match (requested_expiry, max_expiry) {
+                (Some(requested), Some(max)) => Some(requested.min(max)),
+                (requested, max) => requested.or(max),
+            },
+        })
+    }
+}
+
 pub struct AppState {
     pub float_rooms: RoomMap,
     pub chat_rooms: RoomMap,
     pub audio_rooms: RoomMap,
     pub room_dispatchers: Arc<RwLock<HashMap<String, RoomDispatcher>>>,
+    /// name of the return room -> reservation
+    pub return_rooms: Arc<RwLock<HashMap<String, ReturnRoom>>>,
+
+    /// id of the webhook -> webhook
+    pub webhooks: Arc<RwLock<HashMap<String, Webhook>>>,
 
     pub room_events: tokio::sync::broadcast::Sender<RoomEvent>,
     pub jinja: Arc<minijinja::Environment<'static>>,
+    pub room_timeouts: RoomTimeouts,
+    pub config: Config,
+    pub shutdown: Shutdown,
+    pub readiness: Readiness,
+    /// signs the visitor cookies of dispatchers, generated on startup
+    pub cookie_key: Key,
+}
+
+impl FromRef<Arc<AppState>> for Key {
+    fn from_ref(state: &Arc<AppState>) -> Self {
+        state.cookie_key.clone()
+    }
 }
 
 impl AppState {
-    pub fn new() -> Self {
-        let mut env = minijinja::Environment::new();
-        let template_dir = std::env::current_dir().unwrap().join("templates");
-        env.set_loader(minijinja::path_loader(template_dir));
+    pub fn new(config: Config) -> Self {
+        let env = template_environment(config.paths.overrides.clone());
 
-        let (room_event_rx, _) = tokio::sync::broadcast::channel(32);
+        let (room_event_rx, _) =
+            tokio::sync::broadcast::channel(config.limits.room_events_capacity);
         Self {
             float_rooms: RoomMap {
                 map: Arc::new(RwLock::new(HashMap::new())),
+                room_type: RoomType::Float,
                 room_events: room_event_rx.clone(),
             },
             chat_rooms: RoomMap {
                 map: Arc::new(RwLock::new(HashMap::new())),
+                room_type: RoomType::Chat,
                 room_events: room_event_rx.clone(),
             },
             audio_rooms: RoomMap {
                 map: Arc::new(RwLock::new(HashMap::new())),
+                room_type: RoomType::Audio,
                 room_events: room_event_rx.clone(),
             },
             room_dispatchers: Arc::new(RwLock::new(HashMap::new())),
+            return_rooms: Arc::new(RwLock::new(HashMap::new())),
+            webhooks: Arc::new(RwLock::new(HashMap::new())),
             room_events: room_event_rx,
             jinja: Arc::new(env),
+            room_timeouts: config.limits.room_timeouts(),
+            config,
+            shutdown: Shutdown::default(),
+            readiness: Readiness::default(),
+            cookie_key: Key::generate(),
         }
     }
 
@@ -51,6 +205,639 @@ impl AppState {
         self.chat_rooms.reset_state().await;
         self.float_rooms.reset_state().await;
         self.audio_rooms.reset_state().await;
+        self.return_rooms.write().await.clear();
+    }
+
+    pub fn server_info(&self) -> ServerInfo {
+        let mut server_features = vec![
+            features::DISPATCHERS,
+            features::RETURN_ROOMS,
+            features::EVENT_STREAM,
+            features::REST_API,
+        ];
+        // webhooks can only be registered with the admin password
+        if self.config.admin.password.is_some() {
+            server_features.push(features::WEBHOOKS);
+        }
+        ServerInfo {
+            api_version: API_VERSION.to_string(),
+            room_types: vec![RoomType::Float, RoomType::Chat, RoomType::Audio],
+            audio_codecs: vec!["opus".to_string()],
+            trickle_ice: false,
+            features: server_features
+                .into_iter()
+                .map(|feature| feature.to_string())
+                .collect(),
+        }
+    }
+
+    /// administrative operations are only available if an admin password has been configured
+    pub fn check_admin_password(&self, password: &str) -> anyhow::Result<()> {
+        match &self.config.admin.password {
+            Some(admin_password) if admin_password == password => Ok(()),
+            Some(_) => Err(anyhow::anyhow!("Invalid admin password")),
+            None => Err(anyhow::anyhow!(
+                "Administrative operations are disabled - no admin password has been configured"
+            )),
+        }
+    }
+
+    pub async fn create_webhook(
+        &self,
+        url: String,
+        events: Vec<WebhookEvent>,
+        secret: Option<String>,
+    ) -> anyhow::Result<WebhookCreationReply> {
+        let parsed_url = reqwest::Url::parse(&url)?;
+        if !["http", "https"].contains(&parsed_url.scheme()) {
+            return Err(anyhow::anyhow!("Webhook URL needs to use http or https"));
+        }
+        if events.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Webhook needs to subscribe to at least one event"
+            ));
+        }
+        let secret = match secret {
+            Some(secret) if secret.is_empty() => {
+                return Err(anyhow::anyhow!("Webhook secret must not be empty"))
+            }
+            Some(secret) => secret,
+            None => Alphanumeric.sample_string(&mut rand::thread_rng(), 32),
+        };
+
+        let webhook = Webhook {
+            id: Uuid::new_v4().to_string(),
+            url,
+            events,
+            secret: secret.clone(),
+        };
+        info!(webhook.id, webhook.url, "Created webhook");
+        self.webhooks
+            .write()
+            .await
+            .insert(webhook.id.clone(), webhook.clone());
+        Ok(WebhookCreationReply { webhook, secret })
+    }
+
+    pub async fn delete_webhook(&self, id: &str) -> bool {
+        self.webhooks.write().await.remove(id).is_some()
+    }
+
+    /// stops accepting new rooms, notifies all senders, listeners and OSC clients
+    /// and closes all connections after the drain period
+    pub async fn shut_down(&self) {
+        let drain_period = Duration::from_secs(self.config.limits.shutdown_drain_period);
+        let drain_seconds: i32 = drain_period.as_secs().try_into().unwrap_or(i32::MAX);
+
+        for room_map in [&self.float_rooms, &self.chat_rooms, &self.audio_rooms] {
+            for room in room_map.map.read().await.values() {
+                let room = room.read().await;
+                let message = MetaMessage::RoomClosing {
+                    room: room.meta().name.clone(),
+                    drain_period: drain_seconds,
+                }
+                .encode();
+                // the reply goes to the sender, the broadcast to the listeners
+                let _ = room.meta().meta_reply.send(message.clone());
+                let _ = room.meta().meta_broadcast.send(message.clone());
+            }
+        }
+        let _ = self
+            .room_events
+            .send(RoomEvent::ServerShutdown(drain_seconds));
+
+        self.shutdown.drain(drain_period).await;
+    }
+
+    /// sends a notice to the senders and listeners of all rooms
+    pub async fn send_server_notice(&self, message: String) -> usize {
+        let notice = MetaMessage::ServerNotice { message }.encode();
+        let mut num_rooms = 0;
+        for room_map in [&self.float_rooms, &self.chat_rooms, &self.audio_rooms] {
+            for room in room_map.map.read().await.values() {
+                let room = room.read().await;
+                let _ = room.meta().meta_reply.send(notice.clone());
+                let _ = room.meta().meta_broadcast.send(notice.clone());
+                num_rooms += 1;
+            }
+        }
+        num_rooms
+    }
+
+    /// creates a new room - if the room already exists and the password matches,
+    /// the sender of the room gets replaced
+    pub async fn create_room(
+        &self,
+        name: String,
+        offer: String,
+        room_type: RoomType,
+        password: Option<String>,
+        inactivity_timeout: Option<i32>,
+        expires_at: Option<i64>,
+        replay_length: Option<i32>,
+        jam: Option<JamInput>,
+        crowd: Option<CrowdInput>,
+    ) -> anyhow::Result<RoomCreationReply> {
+        self.shutdown.ensure_running()?;
+
+        let replay_length = self.replay_length(&room_type, replay_length)?;
+        let jam = jam.map(|jam| JamMixer::new(jam, &room_type)).transpose()?;
+        let crowd = crowd
+            .map(|crowd| CrowdAggregator::new(crowd, &room_type))
+            .transpose()?;
+        if jam.is_some() && crowd.is_some() {
+            return Err(anyhow::anyhow!(
+                "A room can either be a jam or a crowd room"
+            ));
+        }
+
+        if !self.return_room_claimable(&name, password.as_deref()).await {
+            return Err(anyhow::anyhow!(
+                "The room name is reserved as a return room."
+            ));
+        }
+
+        if self.room_exists(&name, &room_type).await {
+            if let Some(user_provided_password) = password {
+                if self
+                    .room_password_match(&name, &room_type, &user_provided_password)
+                    .await
+                {
+                    trace!("Matched password of existing room");
+                    let offer = self
+                        .replace_sender(&name, &room_type, &user_provided_password, &offer)
+                        .await?;
+
+                    let _ = self
+                        .room_events
+                        .send(RoomEvent::BroadcastRoomUpdated(room_type, name.clone()));
+
+                    return Ok(RoomCreationReply {
+                        offer,
+                        password: user_provided_password,
+                    });
+                }
+            };
+            return Err(anyhow::anyhow!("The room name is already taken."));
+        }
+
+        let room_password: String = if let Some(user_provided_password) = password {
+            user_provided_password
+        } else {
+            Alphanumeric.sample_string(&mut rand::thread_rng(), 8)
+        };
+
+        let lifetime =
+            self.room_timeouts
+                .room_lifetime(&room_type, inactivity_timeout, expires_at)?;
+
+        let name2 = name.clone();
+        let name3 = name.clone();
+        let room_password2 = room_password.clone();
+        match room_type {
+            RoomType::Float | RoomType::Chat => {
+                let result = DataBroadcastRoom::create_room(
+                    name,
+                    offer,
+                    room_type.into(),
+                    room_password,
+                    self.room_events.clone(),
+                    lifetime,
+                    self.shutdown.clone(),
+                    match room_type {
+                        RoomType::Chat => self.config.limits.chat_history_length,
+                        _ => 0,
+                    },
+                    replay_length,
+                    jam,
+                    crowd,
+                )
+                .instrument(Span::current())
+                .await?;
+                let crowd_room = result.broadcast_room.crowd.clone().map(|crowd| {
+                    (
+                        crowd,
+                        result.broadcast_room.meta.uuid,
+                        result.broadcast_room.reply.clone(),
+                        result.broadcast_room.meta.meta_reply.clone(),
+                    )
+                });
+                {
+                    let mut room_lock = match room_type {
+                        RoomType::Float => {
+                            info!("Created a float room");
+                            self.float_rooms.map.write().await
+                        }
+                        RoomType::Chat => {
+                            info!("Created a chat room");
+                            self.chat_rooms.map.write().await
+                        }
+                        RoomType::Audio => {
+                            todo!("This can not happen - can we inherit the types from above?")
+                        }
+                    };
+
+                    self.watch_data_room(
+                        room_type,
+                        name2.clone(),
+                        &result.broadcast_room,
+                        lifetime,
+                    );
+                    room_lock.insert(
+                        name2,
+                        Arc::new(RwLock::new(BroadcastRoom::Data(result.broadcast_room))),
+                    );
+                }
+                let _ = self
+                    .room_events
+                    .send(RoomEvent::BroadcastRoomCreated(room_type, name3.clone()));
+                if let Some((crowd, room_uuid, reply, meta_reply)) = crowd_room {
+                    self.publish_crowd_aggregates(name3, room_uuid, crowd, reply, meta_reply);
+                }
+                Ok(RoomCreationReply {
+                    offer: result.offer,
+                    password: room_password2,
+                })
+            }
+            RoomType::Audio => {
+                let result = AudioBroadcastRoom::create_room(
+                    name,
+                    offer,
+                    room_password,
+                    self.room_events.clone(),
+                    lifetime,
+                    self.shutdown.clone(),
+                )
+                .in_current_span()
+                .await?;
+                {
+                    let mut room_lock = match room_type {
+                        RoomType::Audio => self.audio_rooms.map.write().await,
+                        _ => {
+                            todo!("This can not happen - can we inherit the types from above?")
+                        }
+                    };
+
+                    let mut stream_sequence_number = result
+                        .audio_broadcast_room
+                        .stecker_audio_channel
+                        .sequence_number_receiver
+                        .clone();
+
+                    let room_uuid = result.audio_broadcast_room.meta.uuid;
+                    let room = Arc::new(RwLock::new(BroadcastRoom::Audio(
+                        result.audio_broadcast_room,
+                    )));
+                    let name4 = name2.clone();
+                    room_lock.insert(name2, room.clone());
+
+                    let room_map = self.audio_rooms.clone();
+
+                    tokio::spawn(
+                        async move {
+                            loop {
+                                tokio::select! {
+                                    _ = stream_sequence_number.changed() => {},
+                                    _ = sleep(lifetime.inactivity_timeout) => {
+                                        info!("Timeout for not receiving any package from the sender");
+                                        break;
+                                    },
+                                    _ = room_expiry(lifetime.expires_at) => {
+                                        info!("Room has expired");
+                                        break;
+                                    }
+                                }
+                            }
+                            room_map.remove_room(&name4, &room_uuid).await;
+                            info!("Cleared room");
+                        }
+                        .in_current_span(),
+                    );
+                }
+                info!("Created an audio room");
+
+                let _ = self.room_events.send(RoomEvent::BroadcastRoomCreated(
+                    RoomType::Audio,
+                    name3.clone(),
+                ));
+
+                Ok(RoomCreationReply {
+                    offer: result.offer,
+                    password: room_password2,
+                })
+            }
+        }
+    }
+
+    /// number of recent messages which get replayed to new listeners of a room,
+    /// float rooms replay their last value by default
+    fn replay_length(
+        &self,
+        room_type: &RoomType,
+        replay_length: Option<i32>,
+    ) -> anyhow::Result<usize> {
+        let replay_length = match replay_length {
+            Some(replay_length) => usize::try_from(replay_length)?,
+            None => match room_type {
+                RoomType::Float => 1,
+                RoomType::Chat => self.config.limits.chat_replay_length,
+                RoomType::Audio => 0,
+            },
+        };
+        if replay_length > MAX_REPLAY_LENGTH {
+            return Err(anyhow::anyhow!(
+                "At most {MAX_REPLAY_LENGTH} messages can be replayed to new listeners"
+            ));
+        }
+        Ok(replay_length)
+    }
+
+    /// removes a data room once its creator closed the data channel, did not
+    /// send anything within the inactivity timeout or the room has expired
+    fn watch_data_room(
+        &self,
+        room_type: RoomType,
+        name: String,
+        room: &DataBroadcastRoom,
+        lifetime: RoomLifetime,
+    ) {
+        let mut creator_messages = room.broadcast.subscribe();
+        let mut creator_close = room.close.subscribe();
+        let room_uuid = room.meta.uuid;
+        let meta_reply = room.meta.meta_reply.clone();
+        let meta_broadcast = room.meta.meta_broadcast.clone();
+        let room_map = match room_type {
+            RoomType::Float => self.float_rooms.clone(),
+            _ => self.chat_rooms.clone(),
+        };
+
+        let data_messages = counter!(
+            "stecker_data_messages_total",
+            "type" => room_type.to_string(),
+            "room" => name.clone(),
+        );
+
+        tokio::spawn(
+            async move {
+                loop {
+                    tokio::select! {
+                        msg = creator_messages.recv() => {
+                            match msg {
+                                Ok(_) => data_messages.increment(1),
+                                Err(RecvError::Closed) => break,
+                                Err(RecvError::Lagged(_)) => {},
+                            }
+                        },
+                        _ = creator_close.recv() => {
+                            info!("Creator closed the data channel");
+                            break;
+                        },
+                        _ = sleep(lifetime.inactivity_timeout) => {
+                            info!("Timeout for not receiving any message from the creator");
+                            break;
+                        },
+                        _ = room_expiry(lifetime.expires_at) => {
+                            info!("Room has expired");
+                            break;
+                        }
+                    }
+                }
+                let closing = MetaMessage::RoomClosing {
+                    room: name.clone(),
+                    drain_period: 0,
+                }
+                .encode();
+                let _ = meta_reply.send(closing.clone());
+                let _ = meta_broadcast.send(closing);
+                room_map.remove_room(&name, &room_uuid).await;
+                info!("Cleared room");
+            }
+            .in_current_span(),
+        );
+    }
+
+    /// publishes the aggregated values of the listeners of a crowd room to its creator -
+    /// the mean gets sent on the data channel of the creator, all aggregates as
+    /// `crowdAggregates` message on its meta channel
+    fn publish_crowd_aggregates(
+        &self,
+        name: String,
+        room_uuid: Uuid,
+        crowd: CrowdAggregator,
+        reply: tokio::sync::broadcast::Sender<SteckerData>,
+        meta_reply: tokio::sync::broadcast::Sender<SteckerData>,
+    ) {
+        let room_map = self.float_rooms.clone();
+        tokio::spawn(
+            async move {
+                let mut ticker = interval(crowd.interval);
+                // the mean repeats its last value as long as nobody contributes
+                let mut last_mean = 0.0;
+                loop {
+                    ticker.tick().await;
+                    let crowd_room_exists = match room_map.map.read().await.get(&name) {
+                        Some(room) => room.read().await.meta().uuid == room_uuid,
+                        None => false,
+                    };
+                    if !crowd_room_exists {
+                        break;
+                    }
+
+                    let aggregates = crowd.aggregates();
+                    last_mean = aggregates.mean.unwrap_or(last_mean);
+                    let _ = reply.send(SteckerData::F32(last_mean));
+                    let _ = meta_reply.send(aggregates.meta_message(name.clone()).encode());
+                }
+                info!("Stopped publishing crowd aggregates");
+            }
+            .in_current_span(),
+        );
+    }
+
+    /// history to publish values into a float room - if the room does not exist,
+    /// a float room owned by the server gets created with the given password
+    pub async fn float_room_sender(
+        &self,
+        name: &str,
+        password: &str,
+    ) -> anyhow::Result<RoomHistory> {
+        self.shutdown.ensure_running()?;
+
+        // the room gets looked up and created under the same lock,
+        // so concurrent pushes into a new room do not create it twice
+        let mut rooms = self.float_rooms.map.write().await;
+        if let Some(room) = rooms.get(name) {
+            let room = room.read().await;
+            if room.meta().admin_password != password {
+                return Err(anyhow::anyhow!("Password does not match"));
+            }
+            return match &*room {
+                BroadcastRoom::Data(data_room) => Ok(data_room.history.clone()),
+                BroadcastRoom::Audio(_) => Err(anyhow::anyhow!("{name} is not a float room")),
+            };
+        }
+
+        if !self.return_room_claimable(name, Some(password)).await {
+            return Err(anyhow::anyhow!(
+                "The room name is reserved as a return room."
+            ));
+        }
+        let lifetime = self
+            .room_timeouts
+            .room_lifetime(&RoomType::Float, None, None)?;
+        let room = DataBroadcastRoom::create_server_room(
+            name.to_string(),
+            RoomType::Float.into(),
+            password.to_string(),
+            self.room_events.clone(),
+            lifetime,
+            self.shutdown.clone(),
+            0,
+            self.replay_length(&RoomType::Float, None)?,
+        );
+        let sender = room.history.clone();
+        self.watch_data_room(RoomType::Float, name.to_string(), &room, lifetime);
+        rooms.insert(
+            name.to_string(),
+            Arc::new(RwLock::new(BroadcastRoom::Data(room))),
+        );
+        let _ = self.room_events.send(RoomEvent::BroadcastRoomCreated(
+            RoomType::Float,
+            name.to_string(),
+        ));
+        info!(name, "Created a float room owned by the server");
+        Ok(sender)
+    }
+
+    /// chat room with the given name
+    async fn chat_room(&self, name: &str) -> anyhow::Result<Arc<RwLock<BroadcastRoom>>> {
+        self.chat_rooms
+            .map
+            .read()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or_else(|| StateError::NotFound(format!("No such chat room {name}")).into())
+    }
+
+    /// broadcasts a message to all listeners of a chat room
+    pub async fn send_chat_message(
+        &self,
+        name: &str,
+        text: String,
+        password: &str,
+    ) -> anyhow::Result<ChatMessage> {
+        if text.len() > MAX_CHAT_MESSAGE_LENGTH {
+            return Err(anyhow::anyhow!(
+                "Chat messages may be at most {MAX_CHAT_MESSAGE_LENGTH} bytes long"
+            ));
+        }
+        let room = self.chat_room(name).await?;
+        let room = room.read().await;
+        if room.meta().admin_password != password {
+            return Err(anyhow::anyhow!("Password does not match"));
+        }
+        let BroadcastRoom::Data(data_room) = &*room else {
+            return Err(anyhow::anyhow!("{name} is not a chat room"));
+        };
+        data_room
+            .history
+            .publish(SteckerData::String(text.clone()))
+            .map_err(|_| anyhow::anyhow!("Chat room {name} has been closed"))?;
+        Ok(ChatMessage {
+            text,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)?
+                .as_millis()
+                .try_into()?,
+        })
+    }
+
+    /// the most recent messages of a chat room, oldest first
+    pub async fn chat_messages(&self, name: &str, last: usize) -> anyhow::Result<Vec<ChatMessage>> {
+        let room = self.chat_room(name).await?;
+        let room = room.read().await;
+        let BroadcastRoom::Data(data_room) = &*room else {
+            return Err(anyhow::anyhow!("{name} is not a chat room"));
+        };
+        Ok(data_room
+            .history
+            .last(last)
+            .into_iter()
+            .filter_map(|entry| match entry.data {
+                SteckerData::String(text) => Some(ChatMessage {
+                    text,
+                    timestamp: entry.timestamp,
+                }),
+                SteckerData::F32(_) => None,
+            })
+            .collect())
+    }
+
+    /// joins a room as listener and returns the answer to the offer
+    pub async fn join_room(
+        &self,
+        name: &str,
+        offer: &str,
+        room_type: &RoomType,
+        participant: Option<JamParticipantInput>,
+    ) -> anyhow::Result<String> {
+        self.shutdown.ensure_running()?;
+
+        match room_type {
+            RoomType::Float => match self.float_rooms.map.read().await.get(name) {
+                Some(broadcast_room) => Ok(broadcast_room
+                    .read()
+                    .await
+                    .join_room(&offer, participant)
+                    .instrument(Span::current())
+                    .await?),
+                None => Err(StateError::NotFound(format!("No such room {name}")).into()),
+            },
+            RoomType::Chat => match self.chat_rooms.map.read().await.get(name) {
+                Some(broadcast_room) => Ok(broadcast_room
+                    .read()
+                    .await
+                    .join_room(&offer, participant)
+                    .instrument(Span::current())
+                    .await?),
+                None => Err(StateError::NotFound(format!("No such room {name}")).into()),
+            },
+            RoomType::Audio => match self.audio_rooms.map.read().await.get(name) {
+                Some(broadcast_room) => Ok(broadcast_room
+                    .read()
+                    .await
+                    .join_room(&offer, participant)
+                    .instrument(Span::current())
+                    .await?),
+                None => Err(StateError::NotFound(format!("No such room {name}")).into()),
+            },
+        }
+    }
+
+    /// room chosen by the dispatcher
+    pub async fn access_dispatcher(&self, name: &str) -> anyhow::Result<Room> {
+        if let Some(dispatcher) = self.room_dispatchers.read().await.get(name) {
+            dispatcher.renew();
+            match dispatcher.room_type {
+                RoomType::Float => self.float_rooms.get_room(dispatcher, None).await,
+                RoomType::Chat => self.chat_rooms.get_room(dispatcher, None).await,
+                RoomType::Audio => self.audio_rooms.get_room(dispatcher, None).await,
+            }
+        } else {
+            Err(
+                StateError::NotFound("Could not find a dispatcher with the given name".to_string())
+                    .into(),
+            )
+        }
+    }
+
+    pub async fn get_rooms(&self, room_type: &RoomType) -> Vec<Room> {
+        match room_type {
+            RoomType::Float => self.float_rooms.get_rooms().await,
+            RoomType::Chat => self.chat_rooms.get_rooms().await,
+            RoomType::Audio => self.audio_rooms.get_rooms().await,
+        }
     }
 
     pub async fn room_exists(&self, room_name: &str, room_type: &RoomType) -> bool {
@@ -74,8 +861,10 @@ impl AppState {
                     .replace_sender(room_name, offer, password)
                     .await
             }
-            RoomType::Float => todo!(),
-            RoomType::Chat => todo!(),
+            RoomType::Float | RoomType::Chat => Err(StateError::NotSupported(
+                "The sender of a data room can not be replaced".to_string(),
+            )
+            .into()),
         }
     }
 
@@ -110,17 +899,15 @@ impl AppState {
     ) -> anyhow::Result<RoomDispatcher> {
         let name = dispatcher_input.name.clone();
         let admin_password = dispatcher_input.admin_password.clone();
-        let timeout_value = dispatcher_input.timeout;
 
-        let room_dispatcher: RoomDispatcher = dispatcher_input.into();
+        let room_dispatcher = RoomDispatcher::try_from(dispatcher_input)?;
 
         if let Some(existing_dispatcher) = self.room_dispatchers.write().await.get_mut(&name) {
             if let Some(pw) = admin_password {
-                if pw == existing_dispatcher.admin_password {
+                if existing_dispatcher.password_matches(&pw) {
                     existing_dispatcher.rule = room_dispatcher.rule;
-                    let _ = existing_dispatcher
-                        .timeout_sender
-                        .send(Duration::from_secs(timeout_value.try_into()?));
+                    existing_dispatcher.timeout = room_dispatcher.timeout;
+                    existing_dispatcher.renew();
                     return Ok(existing_dispatcher.clone());
                 } else {
                     return Err(anyhow::anyhow!(
@@ -152,27 +939,182 @@ impl AppState {
         tokio::spawn(
             async move {
                 loop {
-                    let duration = *timeout_receiver.borrow();
+                    let deadline = *timeout_receiver.borrow();
                     tokio::select! {
                         _ = timeout_receiver.changed() => {}
-                        _ = sleep(duration) => {break}
+                        _ = sleep_until(deadline.into()) => {break}
                     }
                 }
                 info!("Dispatcher timed out - will be deleted now");
-                let _ =
-                    dispatcher_deleted_event.send(RoomEvent::BroadcastRoomDeleted(name2.clone()));
                 dispatcher_map_lock.write().await.remove(&name2);
+                let _ =
+                    dispatcher_deleted_event.send(RoomEvent::RoomDispatcherDeleted(name2.clone()));
             }
             .in_current_span(),
         );
 
         Ok(room_dispatcher)
     }
+
+    /// Reserves the name of a return room for a listener which gets dispatched to `source_room`
+    /// and notifies the sender of the source room about it.
+    /// A visitor which already reserved a return room for the source room keeps it.
+    /// Returns the name and password of the return room if the dispatcher uses return rooms.
+    pub async fn reserve_return_room(
+        &self,
+        dispatcher: &RoomDispatcher,
+        source_room: &str,
+        visitor: &str,
+    ) -> Option<(String, String)> {
+        let prefix = dispatcher.return_room_prefix.clone()?;
+
+        // drop reservations which have not been claimed in time
+        let existing_rooms = self.get_rooms(&dispatcher.room_type).await;
+        let mut return_rooms = self.return_rooms.write().await;
+        return_rooms.retain(|return_room_name, return_room| {
+            return_room.reserved_at.elapsed() < RETURN_ROOM_RESERVATION_TIMEOUT
+                || existing_rooms
+                    .iter()
+                    .any(|room| &room.name == return_room_name)
+        });
+
+        if let Some((name, return_room)) = return_rooms.iter().find(|(_, return_room)| {
+            return_room.source_room == source_room && return_room.visitor == visitor
+        }) {
+            return Some((name.clone(), return_room.password.clone()));
+        }
+
+        // a name which is already reserved or taken by a room gets a random postfix
+        // even if the dispatcher does not add one, so reservations do not replace each other
+        let is_taken = |name: &str| {
+            return_rooms.contains_key(name) || existing_rooms.iter().any(|room| room.name == name)
+        };
+        let mut name = format!("{prefix}{source_room}");
+        if dispatcher.add_random_postfix || is_taken(&name) {
+            name = loop {
+                let postfix = Alphanumeric.sample_string(&mut rand::thread_rng(), 6);
+                let candidate = format!("{prefix}{source_room}{postfix}");
+                if !is_taken(&candidate) {
+                    break candidate;
+                }
+            };
+        }
+        let password = Alphanumeric.sample_string(&mut rand::thread_rng(), 8);
+
+        return_rooms.insert(
+            name.clone(),
+            ReturnRoom {
+                source_room: source_room.to_string(),
+                visitor: visitor.to_string(),
+                password: password.clone(),
+                reserved_at: Instant::now(),
+            },
+        );
+        info!(source_room, return_room = name, "Reserved return room");
+
+        let _ = self.room_events.send(RoomEvent::ReturnRoomReserved(
+            source_room.to_string(),
+            name.clone(),
+        ));
+
+        Some((name, password))
+    }
+
+    /// name and password of the return room the visitor reserved for `source_room`
+    pub async fn visitor_return_room(
+        &self,
+        source_room: &str,
+        visitor: &str,
+    ) -> Option<(String, String)> {
+        self.return_rooms
+            .read()
+            .await
+            .iter()
+            .find(|(_, return_room)| {
+                return_room.source_room == source_room && return_room.visitor == visitor
+            })
+            .map(|(name, return_room)| (name.clone(), return_room.password.clone()))
+    }
+
+    /// checks if the room name is not reserved as a return room
+    /// or if the reservation is claimed with the matching password
+    pub async fn return_room_claimable(&self, room_name: &str, password: Option<&str>) -> bool {
+        match self.return_rooms.read().await.get(room_name) {
+            Some(return_room) => password == Some(return_room.password.as_str()),
+            None => true,
+        }
+    }
+
+    pub async fn update_dispatcher(
+        &self,
+        name: &str,
+        admin_password: &str,
+        update: RoomDispatcherUpdate,
+    ) -> anyhow::Result<RoomDispatcher> {
+        let mut dispatchers = self.room_dispatchers.write().await;
+        let dispatcher = dispatchers.get_mut(name).ok_or_else(|| {
+            StateError::NotFound("Could not find a dispatcher with the given name".to_string())
+        })?;
+        if !dispatcher.password_matches(admin_password) {
+            return Err(anyhow::anyhow!("Password of dispatcher does not match"));
+        }
+
+        // all inputs get validated before anything is changed,
+        // so a failing update does not get applied partially
+        let rule = update.rule.map(|rule| Regex::new(&rule)).transpose()?;
+        let timeout = update
+            .timeout
+            .map(|timeout| anyhow::Ok(Duration::from_secs(timeout.try_into()?)))
+            .transpose()?;
+        let sticky_duration = update
+            .sticky_timeout
+            .map(|sticky_timeout| {
+                anyhow::Ok(match sticky_timeout {
+                    0 => None,
+                    timeout => Some(Duration::from_secs(timeout.try_into()?)),
+                })
+            })
+            .transpose()?;
+
+        if let Some(rule) = rule {
+            dispatcher.rule = rule;
+        }
+        if let Some(dispatcher_type) = update.dispatcher_type {
+            dispatcher.dispatcher_type = dispatcher_type;
+        }
+        if let Some(timeout) = timeout {
+            dispatcher.timeout = timeout;
+        }
+        match update.return_room_prefix {
+            MaybeUndefined::Undefined => {}
+            MaybeUndefined::Null => dispatcher.return_room_prefix = None,
+            MaybeUndefined::Value(prefix) => dispatcher.return_room_prefix = Some(prefix),
+        }
+        if let Some(add_random_postfix) = update.add_random_postfix {
+            dispatcher.add_random_postfix = add_random_postfix;
+        }
+        if let Some(max_listeners) = update.max_listeners {
+            dispatcher.max_listeners = max_listeners;
+        }
+        if let Some(sticky_duration) = sticky_duration {
+            dispatcher.sticky_duration = sticky_duration;
+        }
+        dispatcher.renew();
+        info!("Updated dispatcher");
+
+        let _ = self
+            .room_events
+            .send(RoomEvent::RoomDispatcherUpdated(name.to_string()));
+
+        Ok(dispatcher.clone())
+    }
 }
 
+#[derive(Clone)]
 pub struct RoomMap {
     pub map: Arc<RwLock<HashMap<String, Arc<RwLock<BroadcastRoom>>>>>,
 
+    room_type: RoomType,
     room_events: tokio::sync::broadcast::Sender<RoomEvent>,
 }
 
@@ -184,6 +1126,8 @@ pub trait RoomMapTrait {
         room: Arc<RwLock<BroadcastRoom>>,
     ) -> impl Future<Output = ()>;
     fn room_exists(&self, room_name: &str) -> impl Future<Output = bool>;
+    /// removes the room unless it has been replaced by another room with the same name
+    fn remove_room(&self, room_name: &str, uuid: &Uuid) -> impl Future<Output = ()>;
     fn get_rooms(&self) -> impl Future<Output = Vec<Room>>;
     fn room_password_match(
         &self,
@@ -198,7 +1142,11 @@ pub trait RoomMapTrait {
         password: &str,
     ) -> impl Future<Output = anyhow::Result<String>>;
 
-    fn get_room(&self, dispatcher: &RoomDispatcher) -> impl Future<Output = anyhow::Result<Room>>;
+    fn get_room(
+        &self,
+        dispatcher: &RoomDispatcher,
+        visitor: Option<&str>,
+    ) -> impl Future<Output = anyhow::Result<Room>>;
 }
 
 impl RoomMapTrait for RoomMap {
@@ -208,9 +1156,10 @@ impl RoomMapTrait for RoomMap {
     }
 
     async fn insert_room(&self, room_name: &str, room: Arc<RwLock<BroadcastRoom>>) {
-        let _ = self
-            .room_events
-            .send(RoomEvent::BroadcastRoomCreated(room_name.to_string()));
+        let _ = self.room_events.send(RoomEvent::BroadcastRoomCreated(
+            self.room_type,
+            room_name.to_string(),
+        ));
         self.map.write().await.insert(room_name.to_string(), room);
     }
 
@@ -218,6 +1167,21 @@ impl RoomMapTrait for RoomMap {
         self.map.read().await.contains_key(room_name)
     }
 
+    async fn remove_room(&self, room_name: &str, uuid: &Uuid) {
+        let mut map_lock = self.map.write().await;
+        let is_same_room = match map_lock.get(room_name) {
+            Some(room) => room.read().await.meta().uuid == *uuid,
+            None => false,
+        };
+        if is_same_room {
+            map_lock.remove(room_name);
+            let _ = self.room_events.send(RoomEvent::BroadcastRoomDeleted(
+                self.room_type,
+                room_name.to_string(),
+            ));
+        }
+    }
+
     async fn room_password_match(&self, room_name: &str, password: &str) -> bool {
         if let Some(room) = self.map.read().await.get(room_name) {
             room.read().await.meta().admin_password == password
@@ -251,21 +1215,32 @@ impl RoomMapTrait for RoomMap {
         if let Some(room) = self.map.read().await.get(room_name).cloned() {
             // let room_clone = room.clone();
             if room.read().await.meta().admin_password == password {
-                let _ = self.room_events.send(RoomEvent::BroadcastRoomUpdated(
-                    room.read().await.meta().name.clone(),
-                ));
                 room.write().await.replace_sender(offer, password).await
             } else {
                 return Err(anyhow::anyhow!("Password does not match"));
             }
         } else {
-            return Err(anyhow::anyhow!("Did not find room"));
+            return Err(StateError::NotFound("Did not find room".to_string()).into());
         }
     }
 
-    async fn get_room(&self, dispatcher: &RoomDispatcher) -> anyhow::Result<Room> {
+    async fn get_room(
+        &self,
+        dispatcher: &RoomDispatcher,
+        visitor: Option<&str>,
+    ) -> anyhow::Result<Room> {
         let rooms_guard = self.map.read().await;
 
+        if let Some(visitor) = visitor {
+            if let Some(room_name) = dispatcher.sticky_room(visitor).await {
+                if let Some(room) = rooms_guard.get(&room_name) {
+                    if dispatcher.rule.is_match(&room_name) {
+                        return Ok((&*room.read().await).into());
+                    }
+                }
+            }
+        }
+
         // is this the proper way to do this?
         let matched_rooms: Vec<_> = stream::iter(rooms_guard.values())
             .filter_map(|room| async {
@@ -279,10 +1254,15 @@ impl RoomMapTrait for RoomMap {
             .collect()
             .await;
 
-        dispatcher
-            .dispatcher_type
+        let room = dispatcher
             .choose_room(matched_rooms)
             .await
-            .ok_or(anyhow::anyhow!("Could not find matching room"))
+            .ok_or_else(|| StateError::NotFound("Could not find matching room".to_string()))?;
+
+        if let Some(visitor) = visitor {
+            dispatcher.remember_room(visitor, &room.name).await;
+        }
+
+        Ok(room)
     }
 }
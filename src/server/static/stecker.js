@@ -1,3 +1,38 @@
+/**
+ * version of the protocol of the meta channel, see `MetaMessage` of the shared crate
+ */
+const META_PROTOCOL_VERSION = 1;
+
+/**
+ * milliseconds between pings to measure the round trip time to the server
+ */
+const PING_INTERVAL = 30000;
+
+/**
+ * @param {Object} message - typed message of the meta channel
+ * @returns {string}
+ */
+function formatMetaMessage(message) {
+    switch (message.type) {
+        case "listenerCount":
+            return `Number of listeners @ ${message.room}: ${message.numListeners}`;
+        case "senderChanged":
+            return `The sender of ${message.room} has changed`;
+        case "roomClosing":
+            return `${message.room} gets closed in ${message.drainPeriod} seconds`;
+        case "serverNotice":
+            return `Server notice: ${message.message}`;
+        case "feedback":
+            return `Feedback: ${message.message}`;
+        case "jamMessage":
+            return `${message.sender}: ${message.value}`;
+        case "crowdAggregates":
+            return `Crowd @ ${message.room}: count=${message.count} mean=${message.mean} median=${message.median}`;
+        default:
+            return JSON.stringify(message);
+    }
+}
+
 class SteckerConnection {
     constructor() {
         this.peerConnection = new RTCPeerConnection({
@@ -135,11 +170,17 @@ class SteckerDataChannel {
 Alpine.store("stecker", {
     HOST: `${window.location.protocol}//${window.location.host}/graphql`,
     rooms: [],
+    /**
+     * @type {null | EventSource}
+     */
+    roomEvents: null,
     messages: [],
     /**
      * @type {null | SteckerDataChannel}
      */
     steckerDataChannel: null,
+    metaDataChannel: null,
+    pingInterval: null,
     steckerAudioChannelIn: null,
     steckerAudioChannelOut: null,
 
@@ -153,16 +194,28 @@ Alpine.store("stecker", {
 
     floatValue: 0.0,
     chatValue: "",
+    feedbackValue: "",
 
     isConnecting: false,
     isPlaying: false,
 
     /**
-     *
+     * lists the rooms and keeps the list up to date
      * @param {string} roomType
      * @returns {void}
      */
     async getRooms(roomType) {
+        // subscribe first, so no room event between loading and subscribing gets lost
+        this.watchRooms(roomType);
+        await this.loadRooms(roomType);
+    },
+
+    /**
+     * loads the current rooms without touching the subscription to the room events
+     * @param {string} roomType
+     * @returns {void}
+     */
+    async loadRooms(roomType) {
         let results = await fetch(this.HOST, {
             method: "POST",
 
@@ -194,18 +247,43 @@ Alpine.store("stecker", {
         this.rooms = rawRooms.data.rooms;
     },
 
+    /**
+     * keeps the listed rooms up to date via the server-sent room events
+     * @param {string} roomType
+     * @returns {void}
+     */
+    watchRooms(roomType) {
+        if (this.roomEvents !== null) {
+            this.roomEvents.close();
+        }
+        this.roomEvents = new EventSource(`/events?roomType=${roomType}`);
+        this.roomEvents.addEventListener("roomUserCount", (event) => {
+            let data = JSON.parse(event.data);
+            let room = this.rooms.find((room) => room.name === data.room);
+            if (room) {
+                room.numListeners = data.numListeners;
+            }
+        });
+        this.roomEvents.addEventListener("roomCreated", () => this.loadRooms(roomType));
+        this.roomEvents.addEventListener("roomDeleted", (event) => {
+            let data = JSON.parse(event.data);
+            this.rooms = this.rooms.filter((room) => room.name !== data.room);
+        });
+    },
+
     /**
      *
      * @param {string} name
      * @param {string} roomType
+     * @param {string|null} password - necessary to claim a return room reserved by the server
      * @returns {void}
      */
-    async createRoom(name, roomType) {
+    async createRoom(name, roomType, password=null) {
         let steckerConnection = new SteckerConnection();
 
         // we actually don't need to attach this to our alpine store
         new SteckerDataChannel(steckerConnection, "meta", (msg) => {
-            this.log(`META(${name}): ${msg}`);
+            this.logMetaMessage(name, msg);
         });
 
         switch (roomType) {
@@ -239,8 +317,8 @@ Alpine.store("stecker", {
             },
             body: JSON.stringify({
                 query: `
-                    mutation createRoom($name:String!, $offer:String!, $roomType: RoomType!) {
-                        createRoom(name:$name, offer: $offer, roomType: $roomType) {
+                    mutation createRoom($name:String!, $offer:String!, $roomType: RoomType!, $password: String) {
+                        createRoom(name:$name, offer: $offer, roomType: $roomType, password: $password) {
                             offer,
                             password,
                         }
@@ -250,6 +328,7 @@ Alpine.store("stecker", {
                     name: name,
                     offer: localSessionDescription,
                     roomType: roomType.toUpperCase(),
+                    password: password,
                 },
             }),
         });
@@ -276,6 +355,29 @@ Alpine.store("stecker", {
         this.messages.push(message);
     },
 
+    /**
+     * @param {string} name - name of the room
+     * @param {string} rawMessage - JSON encoded message of the meta channel
+     */
+    logMetaMessage(name, rawMessage) {
+        let message;
+        try {
+            message = JSON.parse(rawMessage);
+        } catch (e) {
+            console.warn(`Received an invalid meta message: ${e}`);
+            return;
+        }
+        if (message.version !== META_PROTOCOL_VERSION) {
+            console.warn(`Unsupported meta protocol version ${message.version}`);
+            return;
+        }
+        if (message.type === "pong") {
+            console.log(`Round trip time: ${Date.now() - message.pingTimestamp}ms`);
+            return;
+        }
+        this.log(`META(${name}): ${formatMetaMessage(message)}`);
+    },
+
     sendFloatValue() {
         this.steckerDataChannel.sendValue(this.floatValue);
     },
@@ -285,6 +387,30 @@ Alpine.store("stecker", {
         this.chatValue = "";
     },
 
+    /**
+     * Sends a short message to the sender of the joined room,
+     * the server forwards at most one message per second.
+     */
+    sendFeedback() {
+        this.metaDataChannel.sendValue(JSON.stringify({
+            version: META_PROTOCOL_VERSION,
+            type: "feedback",
+            message: this.feedbackValue,
+        }));
+        this.feedbackValue = "";
+    },
+
+    sendPing() {
+        if (this.metaDataChannel?.channel.readyState !== "open") {
+            return;
+        }
+        this.metaDataChannel.sendValue(JSON.stringify({
+            version: META_PROTOCOL_VERSION,
+            type: "ping",
+            timestamp: Date.now(),
+        }));
+    },
+
     /**
      *
      * @param {String} dispatcherName
@@ -316,25 +442,26 @@ Alpine.store("stecker", {
      *
      * @param {string} name
      * @param {string} roomType
-     * @param {string|null} returnRoomPrefix
-     * @param {boolean} addRandomPostfix
+     * @param {string|null} returnRoom - name of the return room reserved by the server
+     * @param {string|null} returnRoomPassword
      */
-    async joinRoom(name, roomType, returnRoomPrefix, addRandomPostfix) {
+    async joinRoom(name, roomType, returnRoom=null, returnRoomPassword=null) {
         // @todo derived from graphQL, but in js we use lowercase
         roomType = roomType.toLowerCase();
 
-        if(returnRoomPrefix != null) {
-            let randomString = addRandomPostfix ? (Math.random() + 1).toString(36).substring(7) : '';
-            let returnRoomName = `${returnRoomPrefix}${name}${randomString}`;
-            console.log(`Create return room ${returnRoomName}`);
-            this.createRoom(returnRoomName, roomType);
+        if(returnRoom != null) {
+            console.log(`Create return room ${returnRoom}`);
+            this.createRoom(returnRoom, roomType, returnRoomPassword);
         }
 
         let steckerConnection = new SteckerConnection();
 
-        new SteckerDataChannel(steckerConnection, "meta", (msg) => {
-            this.log(`META(${name}): ${msg}`);
+        // feedback for the sender of the room is sent via the meta channel
+        this.metaDataChannel = new SteckerDataChannel(steckerConnection, "meta", (msg) => {
+            this.logMetaMessage(name, msg);
         });
+        clearInterval(this.pingInterval);
+        this.pingInterval = setInterval(() => this.sendPing(), PING_INTERVAL);
 
         switch (roomType) {
             case "float":
@@ -0,0 +1,83 @@
+use std::sync::OnceLock;
+
+use anyhow::Context as _;
+use axum::{extract::Request, http::HeaderMap, middleware::Next, response::Response};
+use opentelemetry::{
+    propagation::{Extractor, TextMapPropagator},
+    trace::TracerProvider as _,
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    runtime,
+    trace::{Tracer, TracerProvider},
+    Resource,
+};
+use tracing::{info_span, warn, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::config::TelemetryConfig;
+
+static TRACER_PROVIDER: OnceLock<TracerProvider> = OnceLock::new();
+
+/// creates a tracer which exports its spans to the configured OTLP collector,
+/// returns `None` if no collector has been configured
+pub fn init_tracer(config: &TelemetryConfig) -> anyhow::Result<Option<Tracer>> {
+    let Some(endpoint) = &config.otlp_endpoint else {
+        return Ok(None);
+    };
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()
+        .with_context(|| format!("Could not create OTLP exporter for {endpoint}"))?;
+
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new(vec![KeyValue::new(
+            "service.name",
+            config.service_name.clone(),
+        )]))
+        .build();
+    let tracer = provider.tracer("stecker_server");
+
+    TRACER_PROVIDER
+        .set(provider)
+        .map_err(|_| anyhow::anyhow!("Tracer has already been initialized"))?;
+    Ok(Some(tracer))
+}
+
+/// exports all remaining spans
+pub fn shutdown() {
+    if let Some(provider) = TRACER_PROVIDER.get() {
+        if let Err(err) = provider.shutdown() {
+            warn!(?err, "Failed to shut down the tracer");
+        }
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// continues the trace of a client which sent a `traceparent` header
+pub async fn trace_context(request: Request, next: Next) -> Response {
+    let parent = TraceContextPropagator::new().extract(&HeaderExtractor(request.headers()));
+    let span = info_span!(
+        "http_request",
+        method = %request.method(),
+        path = request.uri().path(),
+    );
+    span.set_parent(parent);
+    next.run(request).instrument(span).await
+}
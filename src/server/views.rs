@@ -1,11 +1,35 @@
-use std::sync::Arc;
+use std::{convert::Infallible, sync::Arc, time::Duration};
 
 use axum::{
-    extract::{Path, State},
-    response::{Html, IntoResponse, Redirect},
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Html, IntoResponse, Redirect,
+    },
+};
+use axum_extra::extract::cookie::{Cookie, SignedCookieJar};
+use futures::stream::{self, Stream};
+use metrics::counter;
+use rand::distributions::{Alphanumeric, DistString};
+use serde::Deserialize;
+use tokio::{
+    sync::broadcast::{error::RecvError, Receiver, Sender},
+    time::sleep,
+};
+use tracing::warn;
+
+use crate::{
+    event_service::RoomEvent,
+    models::{Room, RoomDispatcher, RoomType},
+    state::{AppState, RoomMapTrait},
 };
 
-use crate::state::{AppState, RoomMapTrait};
+/// signed cookie which identifies a visitor for sticky dispatcher assignments
+/// and the waiting queue
+const VISITOR_COOKIE: &str = "stecker_visitor";
+/// fallback interval to check for a free room while waiting in a dispatcher queue
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(5);
 
 pub enum Template {
     Debug,
@@ -15,7 +39,14 @@ pub enum Template {
 }
 
 impl Template {
-    fn as_str(&self) -> &'static str {
+    pub const ALL: [Template; 4] = [
+        Template::Debug,
+        Template::Stream,
+        Template::DispatcherNotFound,
+        Template::DispatcherNoRoomAvailable,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
         match self {
             Template::Debug => "debug.html.jinja",
             Template::Stream => "stream.html.jinja",
@@ -40,6 +71,7 @@ pub async fn debug_view(State(state): State<Arc<AppState>>) -> Html<String> {
 pub async fn stream_view(
     State(state): State<Arc<AppState>>,
     Path(room_name): Path<String>,
+    jar: SignedCookieJar,
 ) -> Html<String> {
     let room_guard = state.audio_rooms.map.read().await;
     let room_value = room_guard.get(&room_name);
@@ -52,6 +84,15 @@ pub async fn stream_view(
         None => None,
     };
 
+    // the password of the return room is not part of the url,
+    // so it does not end up in logs or the history of the browser
+    let return_room = match (&room_name, jar.get(VISITOR_COOKIE)) {
+        (Some(room_name), Some(visitor)) => {
+            state.visitor_return_room(room_name, visitor.value()).await
+        }
+        _ => None,
+    };
+
     let template = state
         .jinja
         .get_template(Template::Stream.as_str())
@@ -59,43 +100,82 @@ pub async fn stream_view(
     let rendered = template
         .render(minijinja::context! {
             room_name => room_name,
+            return_room => return_room.as_ref().map(|(name, _)| name),
+            return_room_password => return_room.as_ref().map(|(_, password)| password),
         })
         .expect("Rendering of stream view failed");
 
     Html(rendered)
 }
 
+/// uri of the stream page of a room chosen by a dispatcher,
+/// reserves a return room for the visitor if the dispatcher uses return rooms
+async fn dispatch_uri(
+    state: &AppState,
+    dispatcher: &RoomDispatcher,
+    room: &Room,
+    visitor: &str,
+) -> String {
+    // the stream page looks up the return room of the visitor
+    state
+        .reserve_return_room(dispatcher, &room.name, visitor)
+        .await;
+    // @todo how to make this type safe?
+    format!("/s/{}", room.name)
+}
+
 pub async fn dispatcher_view(
     State(state): State<Arc<AppState>>,
     Path(dispatcher_name): Path<String>,
+    jar: SignedCookieJar,
 ) -> Result<impl axum::response::IntoResponse, axum::http::StatusCode> {
     if let Some(dispatcher) = state.room_dispatchers.read().await.get(&dispatcher_name) {
+        dispatcher.renew();
+        let visitor = match jar.get(VISITOR_COOKIE) {
+            Some(cookie) => cookie.value().to_string(),
+            None => Alphanumeric.sample_string(&mut rand::thread_rng(), 16),
+        };
+        let mut visitor_cookie = Cookie::new(VISITOR_COOKIE, visitor.clone());
+        visitor_cookie.set_path("/");
+        visitor_cookie.make_permanent();
+        let jar = jar.add(visitor_cookie);
+
         match dispatcher.room_type {
-            crate::models::RoomType::Float => todo!(),
-            crate::models::RoomType::Chat => todo!(),
-            crate::models::RoomType::Audio => {
-                let room_result = state.audio_rooms.get_room(dispatcher).await;
+            // the stream page only supports audio rooms
+            RoomType::Float | RoomType::Chat => Err(StatusCode::NOT_IMPLEMENTED),
+            RoomType::Audio => {
+                // visitors which are already waiting have precedence,
+                // unless the visitor is returning to its assigned room
+                let room_result = if dispatcher.num_waiting().await == 0
+                    || dispatcher.sticky_room(&visitor).await.is_some()
+                {
+                    state.audio_rooms.get_room(dispatcher, Some(&visitor)).await
+                } else {
+                    Err(anyhow::anyhow!("Other visitors are waiting for a room"))
+                };
                 match room_result {
                     Ok(room) => {
-                        // @todo how to make this type safe?
-                        let mut uri = format!("/s/{}?", room.name);
-                        if let Some(return_prefix) = dispatcher.return_room_prefix.clone() {
-                            uri.push_str(format!("&returnRoomPrefix={}", return_prefix).as_str());
-                        }
-                        if dispatcher.add_random_postfix {
-                            uri.push_str("&addRandomPostfix=1");
-                        }
-                        Ok(Redirect::to(&uri.as_str()).into_response())
+                        let uri = dispatch_uri(&state, dispatcher, &room, &visitor).await;
+                        Ok((jar, Redirect::to(&uri)).into_response())
                     }
                     Err(_) => {
+                        // the visitor only joins the queue once it opens the event
+                        // stream of the queue, so visitors which never do so can not block it
+                        let queue_position = match dispatcher.queue_position(&visitor).await {
+                            Some(position) => position,
+                            None => dispatcher.num_waiting().await,
+                        };
                         let template = state
                             .jinja
                             .get_template(Template::DispatcherNoRoomAvailable.as_str())
                             .expect("Could not find dispatcher no room available template");
                         let rendered = template
-                            .render(minijinja::context! {})
+                            .render(minijinja::context! {
+                                dispatcher_name => dispatcher_name,
+                                queue_position => queue_position + 1,
+                            })
                             .expect("Failed to render dispatcher no room available template");
-                        Ok(Html(rendered).into_response())
+                        Ok((jar, Html(rendered)).into_response())
                     }
                 }
             }
@@ -106,8 +186,312 @@ pub async fn dispatcher_view(
             .get_template(Template::DispatcherNotFound.as_str())
             .expect("Could not find dispatcher not found template");
         let rendered = template
-            .render(minijinja::context! {})
+            .render(minijinja::context! {
+                dispatcher_name => dispatcher_name,
+            })
             .expect("Failed to render dispatcher not found template");
         Ok(Html(rendered).into_response())
     }
 }
+
+/// removes a visitor from the waiting queue once its event stream gets dropped
+struct QueueGuard {
+    dispatcher: RoomDispatcher,
+    visitor: String,
+    room_events: Sender<RoomEvent>,
+}
+
+impl Drop for QueueGuard {
+    fn drop(&mut self) {
+        let dispatcher = self.dispatcher.clone();
+        let visitor = self.visitor.clone();
+        let room_events = self.room_events.clone();
+        tokio::spawn(async move { dispatcher.dequeue(&visitor, &room_events).await });
+    }
+}
+
+struct QueueWatch {
+    state: Arc<AppState>,
+    dispatcher: RoomDispatcher,
+    visitor: String,
+    room_events: Receiver<RoomEvent>,
+    last_position: Option<usize>,
+    finished: bool,
+    _guard: QueueGuard,
+}
+
+impl QueueWatch {
+    async fn next_event(&mut self) -> Event {
+        loop {
+            // the settings of the dispatcher may have been updated in the meantime,
+            // a dispatcher which got recreated under the same name has another queue
+            let dispatcher = match self
+                .state
+                .room_dispatchers
+                .read()
+                .await
+                .get(&self.dispatcher.name)
+            {
+                Some(dispatcher) if Arc::ptr_eq(&dispatcher.queue, &self.dispatcher.queue) => {
+                    Some(dispatcher.clone())
+                }
+                _ => None,
+            };
+            let Some(dispatcher) = dispatcher else {
+                self.finished = true;
+                return Event::default()
+                    .event("closed")
+                    .data("Dispatcher does not exist anymore");
+            };
+            self.dispatcher = dispatcher;
+
+            let position = match self
+                .dispatcher
+                .enqueue(&self.visitor, &self.state.room_events)
+                .await
+            {
+                Some(position) => position,
+                None => {
+                    self.finished = true;
+                    return Event::default()
+                        .event("closed")
+                        .data("The queue of the dispatcher is full");
+                }
+            };
+
+            if position == 0 {
+                let room_result = match self.dispatcher.room_type {
+                    RoomType::Float => {
+                        self.state
+                            .float_rooms
+                            .get_room(&self.dispatcher, Some(&self.visitor))
+                            .await
+                    }
+                    RoomType::Chat => {
+                        self.state
+                            .chat_rooms
+                            .get_room(&self.dispatcher, Some(&self.visitor))
+                            .await
+                    }
+                    RoomType::Audio => {
+                        self.state
+                            .audio_rooms
+                            .get_room(&self.dispatcher, Some(&self.visitor))
+                            .await
+                    }
+                };
+                if let Ok(room) = room_result {
+                    self.dispatcher
+                        .dequeue(&self.visitor, &self.state.room_events)
+                        .await;
+                    self.finished = true;
+                    return Event::default().event("redirect").data(
+                        dispatch_uri(&self.state, &self.dispatcher, &room, &self.visitor).await,
+                    );
+                }
+            }
+
+            if self.last_position != Some(position) {
+                self.last_position = Some(position);
+                return Event::default()
+                    .event("position")
+                    .data((position + 1).to_string());
+            }
+
+            // rooms become free by changing listener counts or new rooms,
+            // the timeout is only a fallback
+            tokio::select! {
+                _ = self.room_events.recv() => {},
+                _ = sleep(QUEUE_POLL_INTERVAL) => {},
+            }
+        }
+    }
+}
+
+/// Keeps a visitor in the waiting queue of a dispatcher and
+/// streams its position until a room becomes available.
+pub async fn dispatcher_queue_view(
+    State(state): State<Arc<AppState>>,
+    Path(dispatcher_name): Path<String>,
+    jar: SignedCookieJar,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let visitor = jar
+        .get(VISITOR_COOKIE)
+        .map(|cookie| cookie.value().to_string())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let dispatcher = state
+        .room_dispatchers
+        .read()
+        .await
+        .get(&dispatcher_name)
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if dispatcher
+        .enqueue(&visitor, &state.room_events)
+        .await
+        .is_none()
+    {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    let queue_watch = QueueWatch {
+        _guard: QueueGuard {
+            dispatcher: dispatcher.clone(),
+            visitor: visitor.clone(),
+            room_events: state.room_events.clone(),
+        },
+        room_events: state.room_events.subscribe(),
+        state,
+        dispatcher,
+        visitor,
+        last_position: None,
+        finished: false,
+    };
+
+    let event_stream = stream::unfold(queue_watch, |mut queue_watch| async move {
+        if queue_watch.finished {
+            return None;
+        }
+        let event = queue_watch.next_event().await;
+        Some((Ok(event), queue_watch))
+    });
+
+    Ok(Sse::new(event_stream).keep_alive(KeepAlive::default()))
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EventFilterParams {
+    room_type: Option<String>,
+    name: Option<String>,
+}
+
+/// restricts the room events which get streamed to a client
+struct EventFilter {
+    room_type: Option<RoomType>,
+    name: Option<String>,
+}
+
+impl EventFilter {
+    fn matches(&self, event: &RoomEvent) -> bool {
+        // clients always need to know when the server goes away
+        if let RoomEvent::ServerShutdown(_) = event {
+            return true;
+        }
+        if let Some(room_type) = self.room_type {
+            if event.room_type() != Some(room_type) {
+                return false;
+            }
+        }
+        if let Some(name) = &self.name {
+            if event.subject() != Some(name.as_str()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Streams room events as JSON, optionally filtered by
+/// `?roomType=audio` and/or `?name=myRoom`.
+pub async fn events_view(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<EventFilterParams>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let room_type = match params.room_type {
+        Some(room_type) => {
+            Some(RoomType::try_from(room_type).map_err(|_| StatusCode::BAD_REQUEST)?)
+        }
+        None => None,
+    };
+    let filter = EventFilter {
+        room_type,
+        name: params.name,
+    };
+
+    let event_stream = stream::unfold(
+        (state.room_events.subscribe(), filter),
+        |(mut room_events, filter)| async move {
+            loop {
+                match room_events.recv().await {
+                    Ok(room_event) if filter.matches(&room_event) => {
+                        let event = Event::default()
+                            .event(room_event.name())
+                            .data(room_event.to_json().to_string());
+                        return Some((Ok(event), (room_events, filter)));
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(lag)) => {
+                        warn!(lag, "Event stream missed room events");
+                        counter!("stecker_lagged_receivers_total", "source" => "event_stream")
+                            .increment(1);
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        },
+    );
+
+    Ok(Sse::new(event_stream).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{EmptySubscription, Request, Schema, Variables};
+    use serde_json::json;
+    use shared::{
+        connections::{set_ice_servers, SteckerWebRTCConnection},
+        models::DataRoomInternalType,
+    };
+
+    use super::*;
+    use crate::{
+        config::Config,
+        schema::{Mutation, Query},
+    };
+
+    #[tokio::test]
+    async fn events_of_float_rooms_include_created_rooms() {
+        // host candidates are sufficient for a local offer
+        let _ = set_ice_servers(vec![]);
+        let state = Arc::new(AppState::new(Config::default()));
+        let schema = Schema::build(Query, Mutation, EmptySubscription)
+            .data(state.clone())
+            .finish();
+        let mut room_events = state.room_events.subscribe();
+        let filter = EventFilter {
+            room_type: Some(RoomType::Float),
+            name: None,
+        };
+
+        let connection = SteckerWebRTCConnection::build_connection().await.unwrap();
+        connection
+            .create_data_channel(&DataRoomInternalType::Float)
+            .await
+            .unwrap();
+        connection
+            .create_data_channel(&DataRoomInternalType::Meta)
+            .await
+            .unwrap();
+        let offer = connection.create_offer().await.unwrap();
+
+        let response = schema
+            .execute(
+                Request::new(
+                    r#"mutation ($offer: String!) {
+                        createRoom(name: "myRoom", offer: $offer, roomType: FLOAT) { password }
+                    }"#,
+                )
+                .variables(Variables::from_json(json!({ "offer": offer }))),
+            )
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let event = room_events.recv().await.unwrap();
+        assert!(filter.matches(&event));
+        assert_eq!(event.name(), "roomCreated");
+        assert_eq!(event.subject(), Some("myRoom"));
+        connection.close().await.unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use tokio::{sync::broadcast::error::RecvError, time::sleep};
+use tracing::{error, info_span, instrument, trace, warn, Instrument};
+use uuid::Uuid;
+
+use crate::{
+    event_service::RoomEvent,
+    models::{hmac_sha256_hex, RoomType, Webhook, WebhookEvent},
+    state::AppState,
+};
+
+const MAX_DELIVERY_ATTEMPTS: u32 = 5;
+/// delay before the first retry, doubled on each further retry
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub const SIGNATURE_HEADER: &str = "X-Stecker-Signature";
+pub const EVENT_HEADER: &str = "X-Stecker-Event";
+pub const DELIVERY_HEADER: &str = "X-Stecker-Delivery";
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct WebhookPayload {
+    event: String,
+    room: String,
+    room_type: RoomType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_listeners: Option<i32>,
+    /// unix timestamp in seconds
+    timestamp: u64,
+}
+
+impl WebhookPayload {
+    fn new(
+        event: WebhookEvent,
+        room: String,
+        room_type: RoomType,
+        num_listeners: Option<i32>,
+    ) -> Self {
+        Self {
+            event: event.to_string(),
+            room,
+            room_type,
+            num_listeners,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+        }
+    }
+}
+
+impl RoomEvent {
+    fn webhook_payload(&self) -> Option<(WebhookEvent, WebhookPayload)> {
+        let (event, room_type, room, num_listeners) = match self {
+            RoomEvent::BroadcastRoomCreated(room_type, room) => {
+                (WebhookEvent::RoomCreated, room_type, room, None)
+            }
+            RoomEvent::BroadcastRoomUpdated(room_type, room) => {
+                (WebhookEvent::RoomUpdated, room_type, room, None)
+            }
+            RoomEvent::BroadcastRoomDeleted(room_type, room) => {
+                (WebhookEvent::RoomDeleted, room_type, room, None)
+            }
+            RoomEvent::BroadcastRoomUserCount(room_type, room, num_listeners) => (
+                WebhookEvent::RoomUserCount,
+                room_type,
+                room,
+                Some(*num_listeners),
+            ),
+            _ => return None,
+        };
+        Some((
+            event,
+            WebhookPayload::new(event, room.clone(), *room_type, num_listeners),
+        ))
+    }
+}
+
+/// hex encoded HMAC-SHA256 of the body, prefixed with `sha256=`
+fn sign(secret: &str, body: &[u8]) -> String {
+    format!("sha256={}", hmac_sha256_hex(secret, body))
+}
+
+async fn deliver(client: reqwest::Client, webhook: Webhook, event: WebhookEvent, body: String) {
+    let delivery_id = Uuid::new_v4().to_string();
+    let signature = sign(&webhook.secret, body.as_bytes());
+    let mut retry_delay = INITIAL_RETRY_DELAY;
+
+    for attempt in 1..=MAX_DELIVERY_ATTEMPTS {
+        let result = client
+            .post(&webhook.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event.to_string())
+            .header(DELIVERY_HEADER, &delivery_id)
+            .header(SIGNATURE_HEADER, &signature)
+            .body(body.clone())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+
+        match result {
+            Ok(_) => {
+                trace!(attempt, "Delivered webhook");
+                return;
+            }
+            Err(err) => warn!(attempt, ?err, "Failed to deliver webhook"),
+        }
+
+        if attempt < MAX_DELIVERY_ATTEMPTS {
+            sleep(retry_delay).await;
+            retry_delay *= 2;
+        }
+    }
+    error!(delivery_id, "Gave up delivering webhook");
+}
+
+/// posts room events to all webhooks which subscribed to them
+#[instrument(skip_all)]
+pub async fn dispatch_webhooks(state: Arc<AppState>) {
+    let client = reqwest::Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .build()
+        .expect("Failed to build webhook client");
+    let mut room_events = state.room_events.subscribe();
+
+    loop {
+        match room_events.recv().await {
+            Ok(room_event) => {
+                let Some((event, payload)) = room_event.webhook_payload() else {
+                    continue;
+                };
+                let body = match serde_json::to_string(&payload) {
+                    Ok(body) => body,
+                    Err(err) => {
+                        error!(?err, "Failed to serialize webhook payload");
+                        continue;
+                    }
+                };
+                for webhook in state
+                    .webhooks
+                    .read()
+                    .await
+                    .values()
+                    .filter(|webhook| webhook.subscribes_to(&event))
+                {
+                    let span = info_span!(
+                        "webhook_delivery",
+                        webhook_id = %webhook.id,
+                        url = %webhook.url,
+                        %event,
+                    );
+                    tokio::spawn(
+                        deliver(client.clone(), webhook.clone(), event, body.clone())
+                            .instrument(span),
+                    );
+                }
+            }
+            Err(RecvError::Lagged(lag)) => warn!(lag, "Webhooks missed room events"),
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
@@ -1,11 +1,13 @@
 use crate::{
-    models::{DataRoomPublicType, SteckerAPIRoomType},
+    models::{api_versions_compatible, DataRoomPublicType, SteckerAPIRoomType, API_VERSION},
+    telemetry::trace_context_headers,
     utils::decode_b64,
 };
 use anyhow::bail;
 use reqwest::StatusCode;
 use serde::Deserialize;
 use serde_json::json;
+use tokio::sync::OnceCell;
 use tracing::{error, instrument, trace};
 use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
 
@@ -39,14 +41,34 @@ struct JoinRoomData {
     join_room: String,
 }
 
+/// version and capabilities reported by the server
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfo {
+    pub api_version: String,
+    pub room_types: Vec<String>,
+    pub audio_codecs: Vec<String>,
+    pub trickle_ice: bool,
+    pub features: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ServerInfoData {
+    server_info: ServerInfo,
+}
+
 pub struct APIClient {
     graphql_url: String,
+    /// fetched once before the first room gets created or joined
+    server_info: OnceCell<ServerInfo>,
 }
 
 impl APIClient {
     pub fn new(host: String) -> Self {
         Self {
             graphql_url: format!("{host}/graphql"),
+            server_info: OnceCell::new(),
         }
     }
 }
@@ -65,6 +87,65 @@ impl Into<String> for &SteckerAPIRoomType {
 }
 
 impl APIClient {
+    #[instrument(skip_all, err)]
+    pub async fn server_info(&self) -> anyhow::Result<&ServerInfo> {
+        self.server_info
+            .get_or_try_init(|| async {
+                let query = json!({
+                    "query": "query { serverInfo { apiVersion roomTypes audioCodecs trickleIce features } }",
+                });
+
+                let client = reqwest::Client::new();
+                let res = client
+                    .post(&self.graphql_url)
+                    .header("Content-Type", "application/json")
+                    .header("Accept", "application/json")
+                    .headers(trace_context_headers())
+                    .body(query.to_string())
+                    .send()
+                    .await?;
+                let text = res.text().await?;
+
+                match serde_json::from_str::<GQLResponse<ServerInfoData>>(&text) {
+                    Ok(result) => Ok::<_, anyhow::Error>(result.data.server_info),
+                    Err(_) => bail!(
+                        "The Stecker server at {} does not report its API version - it is probably older than this client (API version {API_VERSION}), please update the server",
+                        self.graphql_url
+                    ),
+                }
+            })
+            .await
+    }
+
+    /// fails with a descriptive error if the server can not serve the room type
+    /// or speaks an incompatible API version
+    pub async fn check_compatibility(&self, room_type: &SteckerAPIRoomType) -> anyhow::Result<()> {
+        let server_info = self.server_info().await?;
+
+        if !api_versions_compatible(API_VERSION, &server_info.api_version)? {
+            bail!(
+                "The Stecker server at {} speaks API version {} which is incompatible with this client (API version {API_VERSION}) - please update the outdated side",
+                self.graphql_url,
+                server_info.api_version
+            );
+        }
+
+        let room_string: String = room_type.into();
+        if !server_info.room_types.contains(&room_string) {
+            bail!("The Stecker server does not support {room_string} rooms");
+        }
+        if let SteckerAPIRoomType::Audio = room_type {
+            if !server_info
+                .audio_codecs
+                .iter()
+                .any(|codec| codec.eq_ignore_ascii_case("opus"))
+            {
+                bail!("The Stecker server does not support the Opus codec for audio rooms");
+            }
+        }
+        Ok(())
+    }
+
     #[instrument(skip_all, err)]
     pub async fn create_room(
         &self,
@@ -73,6 +154,7 @@ impl APIClient {
         room_type: &SteckerAPIRoomType,
         local_session_description: &str,
     ) -> anyhow::Result<CreateRoomResponse> {
+        self.check_compatibility(room_type).await?;
         let room_string: String = room_type.into();
 
         // @todo skip serialization of password if none, see https://serde.rs/field-attrs.html#skip_serializing_if
@@ -100,6 +182,7 @@ impl APIClient {
             .post(&self.graphql_url)
             .header("Content-Type", "application/json")
             .header("Accept", "application/json")
+            .headers(trace_context_headers())
             .body(query.to_string())
             .send()
             .await?;
@@ -135,6 +218,7 @@ impl APIClient {
         room_type: &SteckerAPIRoomType,
         local_session_description: &str,
     ) -> anyhow::Result<RTCSessionDescription> {
+        self.check_compatibility(room_type).await?;
         let room_string: String = room_type.into();
         let query = json!({
             "query": "mutation joinRoom($name:String!, $offer:String!, $roomType:RoomType!) { joinRoom(name:$name, offer:$offer, roomType:$roomType) }",
@@ -150,6 +234,7 @@ impl APIClient {
             .post(&self.graphql_url)
             .header("Content-Type", "application/json")
             .header("Accept", "application/json")
+            .headers(trace_context_headers())
             .body(query.to_string())
             .send()
             .await?;
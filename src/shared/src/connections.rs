@@ -5,8 +5,9 @@ use crate::models::{
 use crate::utils::{decode_b64, encode_offer};
 
 use anyhow::anyhow;
+use metrics::counter;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 use tokio::sync::broadcast::{self, Receiver, Sender};
 use tracing::{error, info, instrument, trace, warn, Instrument, Span};
 use webrtc::api::interceptor_registry::register_default_interceptors;
@@ -26,6 +27,20 @@ use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
 use webrtc::track::track_local::track_local_static_sample::TrackLocalStaticSample;
 use webrtc::track::track_remote::TrackRemote;
 
+/// STUN server which is used if no other ICE servers have been configured
+pub const DEFAULT_ICE_SERVER: &str = "stun:stun.l.google.com:19302";
+
+static ICE_SERVERS: OnceLock<Vec<RTCIceServer>> = OnceLock::new();
+
+/// Sets the ICE servers (including the credentials of TURN servers) used by all
+/// connections which get built afterwards.
+/// Can only be set once, e.g. on startup of the server.
+pub fn set_ice_servers(servers: Vec<RTCIceServer>) -> anyhow::Result<()> {
+    ICE_SERVERS
+        .set(servers)
+        .map_err(|_| anyhow!("ICE servers have already been set"))
+}
+
 #[derive(Clone, Debug)]
 pub enum ConnectionEvent {
     NewICEConnectionState(RTCIceConnectionState),
@@ -33,8 +48,10 @@ pub enum ConnectionEvent {
 }
 
 /// This handles all the setup of a WebRTC peer connection.
+/// Clones share the same underlying peer connection.
+#[derive(Clone)]
 pub struct SteckerWebRTCConnection {
-    peer_connection: RTCPeerConnection,
+    peer_connection: Arc<RTCPeerConnection>,
     data_channel_map: Arc<Mutex<DataChannelMap>>,
     pub connection_events: Arc<Sender<ConnectionEvent>>,
 }
@@ -55,14 +72,16 @@ impl SteckerWebRTCConnection {
             .build();
 
         let config = RTCConfiguration {
-            ice_servers: vec![RTCIceServer {
-                urls: vec!["stun:stun.l.google.com:19302".to_owned()],
-                ..Default::default()
-            }],
+            ice_servers: ICE_SERVERS.get().cloned().unwrap_or_else(|| {
+                vec![RTCIceServer {
+                    urls: vec![DEFAULT_ICE_SERVER.to_owned()],
+                    ..Default::default()
+                }]
+            }),
             ..Default::default()
         };
 
-        let peer_connection = api.new_peer_connection(config).await?;
+        let peer_connection = Arc::new(api.new_peer_connection(config).await?);
 
         let (connection_events_sender, _) = broadcast::channel::<ConnectionEvent>(4);
         let sender = Arc::new(connection_events_sender);
@@ -92,6 +111,11 @@ impl SteckerWebRTCConnection {
                 Box::pin(
                     async move {
                         trace!(?state, "New peer connection state");
+                        counter!(
+                            "stecker_webrtc_connection_state_transitions_total",
+                            "state" => state.to_string(),
+                        )
+                        .increment(1);
                         let _ = sender.send(ConnectionEvent::NewPeerConnectionState(state));
                     }
                     .instrument(peer_span.clone()),
@@ -205,6 +229,7 @@ impl SteckerWebRTCConnection {
                     trace!("Started data channel connection thread");
                     let mut outbound_msg_rx = stecker_channel2.outbound.subscribe();
                     let mut close_rx = stecker_channel2.close.subscribe();
+                    let _ = stecker_channel2.open.send(());
 
                     let mut _result = anyhow::Result::<usize>::Ok(0);
 
@@ -223,6 +248,7 @@ impl SteckerWebRTCConnection {
                                             let _ = outbound_msg_rx.recv().await;
                                         }
                                         warn!("Got a lagging error");
+                                        counter!("stecker_lagged_receivers_total", "source" => "data_channel").increment(1);
                                     },
                                 };
                             },
@@ -1,4 +1,5 @@
 pub mod api;
 pub mod connections;
 pub mod models;
+pub mod telemetry;
 pub mod utils;
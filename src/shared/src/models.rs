@@ -5,14 +5,43 @@ use std::{
 };
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
+use serde::{Deserialize, Serialize};
 use tokio::sync::broadcast::{self, Sender};
 use webrtc::{
     data_channel::data_channel_message::DataChannelMessage,
     track::track_local::track_local_static_rtp::TrackLocalStaticRTP,
 };
 
-// @todo use cargo.toml version
-pub static API_VERSION: &'static str = "0.1.0";
+/// version of the protocol between clients and server, independent of the crate
+/// versions - only bump it if clients and servers can not talk to each other anymore
+pub const API_VERSION: &str = "0.1.0";
+
+/// optional features a server announces via its server info
+pub mod features {
+    pub const DISPATCHERS: &str = "dispatchers";
+    pub const RETURN_ROOMS: &str = "returnRooms";
+    pub const EVENT_STREAM: &str = "eventStream";
+    pub const WEBHOOKS: &str = "webhooks";
+    pub const REST_API: &str = "restApi";
+}
+
+fn parse_version(version: &str) -> anyhow::Result<(u64, u64)> {
+    let mut parts = version.trim().split('.');
+    let major = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Invalid version {version}"))?
+        .parse()?;
+    let minor = parts.next().unwrap_or("0").parse()?;
+    Ok((major, minor))
+}
+
+/// a client is able to talk to a server if their major versions match,
+/// for `0.x` versions the minor versions need to match as well
+pub fn api_versions_compatible(client_version: &str, server_version: &str) -> anyhow::Result<bool> {
+    let (client_major, client_minor) = parse_version(client_version)?;
+    let (server_major, server_minor) = parse_version(server_version)?;
+    Ok(client_major == server_major && (client_major > 0 || client_minor == server_minor))
+}
 
 /// the possible kinds of data rooms used
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -81,6 +110,9 @@ pub struct SteckerDataChannel {
     pub outbound: Sender<SteckerData>,
     /// triggers when connection was closed
     pub close: Sender<()>,
+    /// triggers once the data channel has been opened and
+    /// outbound messages get delivered
+    pub open: Sender<()>,
     // necessary for async matching via listening
     // on data channels.
     pub channel_type: SteckerDataChannelType,
@@ -93,11 +125,13 @@ impl SteckerDataChannel {
         let (inbound, _) = broadcast::channel::<SteckerData>(capacity);
         let (outbound, _) = broadcast::channel::<SteckerData>(capacity);
         let (close, _) = broadcast::channel::<()>(1);
+        let (open, _) = broadcast::channel::<()>(1);
 
         SteckerDataChannel {
             inbound,
             outbound,
             close,
+            open,
             channel_type,
         }
     }
@@ -141,6 +175,168 @@ impl Display for SteckerData {
     }
 }
 
+/// version of the protocol of the meta channel, sent along with each [`MetaMessage`]
+pub const META_PROTOCOL_VERSION: u32 = 1;
+
+/// a value sent within a [`MetaMessage`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum MetaValue {
+    Float(f32),
+    Text(String),
+}
+
+impl From<SteckerData> for MetaValue {
+    fn from(value: SteckerData) -> Self {
+        match value {
+            SteckerData::F32(value) => MetaValue::Float(value),
+            SteckerData::String(text) => MetaValue::Text(text),
+        }
+    }
+}
+
+/// messages of the meta channel of a room, which get sent as JSON along with the
+/// protocol version, e.g. `{"version": 1, "type": "listenerCount", "room": "myRoom", "numListeners": 3}`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum MetaMessage {
+    /// the number of listeners of a room has changed
+    ListenerCount {
+        room: String,
+        num_listeners: i32,
+    },
+    /// the sender of a room has been replaced
+    SenderChanged {
+        room: String,
+    },
+    /// all connections of a room get closed after the drain period (in seconds)
+    RoomClosing {
+        room: String,
+        drain_period: i32,
+    },
+    /// a message of the operator of the server
+    ServerNotice {
+        message: String,
+    },
+    /// answered with a [`MetaMessage::Pong`], timestamps are unix timestamps in milliseconds
+    Ping {
+        timestamp: i64,
+    },
+    Pong {
+        ping_timestamp: i64,
+        timestamp: i64,
+    },
+    /// a short message of a listener to the sender of a room
+    Feedback {
+        message: String,
+    },
+    /// a message of a sender of a jam room
+    JamMessage {
+        sender: String,
+        value: MetaValue,
+    },
+    /// aggregated values of the listeners of a crowd room
+    CrowdAggregates {
+        room: String,
+        mean: Option<f32>,
+        median: Option<f32>,
+        count: usize,
+        histogram: Vec<usize>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct MetaEnvelope {
+    version: u32,
+    #[serde(flatten)]
+    message: MetaMessage,
+}
+
+impl MetaMessage {
+    pub fn encode(&self) -> SteckerData {
+        let envelope = MetaEnvelope {
+            version: META_PROTOCOL_VERSION,
+            message: self.clone(),
+        };
+        SteckerData::String(
+            serde_json::to_string(&envelope).expect("Meta messages can always be serialized"),
+        )
+    }
+
+    pub fn decode(data: &SteckerData) -> anyhow::Result<Self> {
+        let SteckerData::String(text) = data else {
+            anyhow::bail!("Meta messages need to be strings");
+        };
+        let envelope: MetaEnvelope = serde_json::from_str(text)?;
+        if envelope.version != META_PROTOCOL_VERSION {
+            anyhow::bail!(
+                "Unsupported meta protocol version {} - expected {META_PROTOCOL_VERSION}",
+                envelope.version
+            );
+        }
+        Ok(envelope.message)
+    }
+
+    /// answer to a [`MetaMessage::Ping`]
+    pub fn pong(ping_timestamp: i64) -> Self {
+        MetaMessage::Pong {
+            ping_timestamp,
+            timestamp: unix_timestamp_millis(),
+        }
+    }
+}
+
+/// current unix timestamp in milliseconds
+pub fn unix_timestamp_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis().try_into().unwrap_or(i64::MAX))
+        .unwrap_or(0)
+}
+
+impl Display for MetaMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetaMessage::ListenerCount {
+                room,
+                num_listeners,
+            } => write!(f, "Number of listeners @ {room}: {num_listeners}"),
+            MetaMessage::SenderChanged { room } => write!(f, "The sender of {room} has changed"),
+            MetaMessage::RoomClosing { room, drain_period } => {
+                write!(f, "{room} gets closed in {drain_period} seconds")
+            }
+            MetaMessage::ServerNotice { message } => write!(f, "Server notice: {message}"),
+            MetaMessage::Ping { timestamp } => write!(f, "Ping @ {timestamp}"),
+            MetaMessage::Pong {
+                ping_timestamp,
+                timestamp,
+            } => write!(f, "Pong @ {timestamp} for ping @ {ping_timestamp}"),
+            MetaMessage::Feedback { message } => write!(f, "Feedback: {message}"),
+            MetaMessage::JamMessage { sender, value } => match value {
+                MetaValue::Float(value) => write!(f, "{sender}: {value}"),
+                MetaValue::Text(text) => write!(f, "{sender}: {text}"),
+            },
+            MetaMessage::CrowdAggregates {
+                room,
+                mean,
+                median,
+                count,
+                histogram,
+            } => {
+                write!(f, "Crowd @ {room}: count={count}")?;
+                if let (Some(mean), Some(median)) = (mean, median) {
+                    write!(f, " mean={mean} median={median}")?;
+                }
+                write!(f, " histogram={histogram:?}")
+            }
+        }
+    }
+}
+
 pub type ChannelName = String;
 
 impl From<&DataRoomInternalType> for ChannelName {
@@ -202,3 +398,72 @@ impl SteckerAudioChannel {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(data: SteckerData) -> String {
+        match data {
+            SteckerData::String(text) => text,
+            SteckerData::F32(value) => panic!("Expected a string, got {value}"),
+        }
+    }
+
+    #[test]
+    fn meta_message_encodes_flattened_envelope() {
+        let message = MetaMessage::ListenerCount {
+            room: "myRoom".to_string(),
+            num_listeners: 3,
+        };
+        let encoded: serde_json::Value = serde_json::from_str(&text(message.encode())).unwrap();
+        assert_eq!(
+            encoded,
+            serde_json::json!({
+                "version": META_PROTOCOL_VERSION,
+                "type": "listenerCount",
+                "room": "myRoom",
+                "numListeners": 3
+            })
+        );
+    }
+
+    #[test]
+    fn meta_message_roundtrip() {
+        let messages = [
+            MetaMessage::Feedback {
+                message: "I can't hear".to_string(),
+            },
+            MetaMessage::JamMessage {
+                sender: "alice".to_string(),
+                value: MetaValue::Float(0.5),
+            },
+            MetaMessage::CrowdAggregates {
+                room: "myCrowd".to_string(),
+                mean: None,
+                median: Some(0.5),
+                count: 1,
+                histogram: vec![0, 1],
+            },
+        ];
+        for message in messages {
+            assert_eq!(MetaMessage::decode(&message.encode()).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn meta_message_decode_rejects_invalid_messages() {
+        let other_version = SteckerData::String(format!(
+            r#"{{"version": {}, "type": "feedback", "message": "hi"}}"#,
+            META_PROTOCOL_VERSION + 1
+        ));
+        assert!(MetaMessage::decode(&other_version).is_err());
+
+        let without_version =
+            SteckerData::String(r#"{"type": "feedback", "message": "hi"}"#.to_string());
+        assert!(MetaMessage::decode(&without_version).is_err());
+
+        assert!(MetaMessage::decode(&SteckerData::String("no json".to_string())).is_err());
+        assert!(MetaMessage::decode(&SteckerData::F32(1.0)).is_err());
+    }
+}
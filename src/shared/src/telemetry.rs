@@ -0,0 +1,48 @@
+use opentelemetry::{
+    propagation::{Injector, TextMapPropagator},
+    trace::{SpanContext, TraceContextExt, TraceFlags, TraceState},
+    Context,
+};
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    trace::{IdGenerator, RandomIdGenerator},
+};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use tracing::{debug, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+/// `traceparent` header of the current span, so the server continues its trace.
+/// If no tracer has been installed a new trace gets started, whose id gets logged
+/// so a request can still be followed on the server.
+pub fn trace_context_headers() -> HeaderMap {
+    let mut context = Span::current().context();
+    if !context.span().span_context().is_valid() {
+        let id_generator = RandomIdGenerator::default();
+        let span_context = SpanContext::new(
+            id_generator.new_trace_id(),
+            id_generator.new_span_id(),
+            TraceFlags::SAMPLED,
+            false,
+            TraceState::default(),
+        );
+        debug!(trace_id = %span_context.trace_id(), "Started new trace");
+        context = Context::new().with_remote_span_context(span_context);
+    }
+
+    let mut headers = HeaderMap::new();
+    TraceContextPropagator::new().inject_context(&context, &mut HeaderInjector(&mut headers));
+    headers
+}